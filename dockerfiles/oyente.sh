@@ -1,7 +1,10 @@
 #! /bin/bash -e
 
-cp -rs /src /out
+# oyente writes next to the contract, the sources are mounted read-only and
+# the root may be too (see sandboxing in the README), so it runs on a copy
+out="${TMPDIR:-/tmp}/out"
+cp -rs /src "$out"
 
-cd /out
+cd "$out"
 
-python /usr/local/lib/python3.6/site-packages/oyente/oyente.py $@
\ No newline at end of file
+exec python /usr/local/lib/python3.6/site-packages/oyente/oyente.py "$@"
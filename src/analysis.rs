@@ -0,0 +1,740 @@
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::thread;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cache::{self, Cache, CacheKey};
+use backend::{Backends, ToolInfo};
+use config::Config;
+use docker::{Sandbox, CONTAINER_MEMORY};
+use error::SolsaError;
+use findings::{self, ToolFindings};
+use images;
+use scheduler::{self, Progress};
+use tools::{self, AnalysisDepth, MythrilOptions, OyenteOptions, ToolResponses, ToolRun};
+
+/// Tools an analysis can run, in the order they are reported
+pub const TOOLS: &[&str] = &["solc", "solium", "mythril", "oyente"];
+
+/// Tools taking minutes rather than seconds, run by `run_slow_tools`
+pub const SLOW_TOOLS: &[&str] = &["mythril", "oyente"];
+
+// fast tools first, so their results are in early
+fn jobs(tools: &[String], inputs: &[String]) -> Vec<Job> {
+    TOOLS
+        .iter()
+        .filter(|t| tools.iter().any(|s| s == *t))
+        .flat_map(|tool| {
+            inputs.iter().map(move |contract| Job {
+                tool: tool.to_string(),
+                contract: contract.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Results of the tools that ran on a single contract
+#[derive(Debug)]
+pub struct ContractResult {
+    pub contract: String,
+    /// Flattened contract source, when asked for with `include_source`
+    pub source: Option<String>,
+    /// Raw tool output, `None` for tools that were not selected
+    pub responses: ToolResponses,
+    /// Normalized findings of every tool, `NotRun` for those not selected
+    pub findings: Vec<ToolFindings>,
+}
+
+/// A single tool run on a single contract
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub tool: String,
+    pub contract: String,
+}
+
+/// A tool run as `Analysis::plan` describes it, without running anything
+#[derive(Debug)]
+pub struct Invocation {
+    pub tool: String,
+    pub contract: String,
+    /// Command line the tool would be run with, or why it could not be
+    pub command: Result<String, SolsaError>,
+}
+
+// The response of whichever tool a job ran
+enum Response {
+    Solc(Option<(tools::SolcResponse, ToolRun)>),
+    Solium(Option<(tools::SoliumResponse, ToolRun)>),
+    Mythril(Option<(tools::MythrilResponse, ToolRun)>),
+    Oyente(Option<(tools::OyenteResponse, ToolRun)>),
+}
+
+fn keep_run<T>(tool: &str, r: Option<(T, ToolRun)>, runs: &mut BTreeMap<String, ToolRun>) -> Option<T> {
+    r.map(|(response, run)| {
+        runs.insert(tool.to_owned(), run);
+        response
+    })
+}
+
+impl Response {
+    fn set(self, responses: &mut ToolResponses) {
+        let runs = &mut responses.runs;
+        match self {
+            Response::Solc(r) => responses.solc = keep_run("solc", r, runs),
+            Response::Solium(r) => responses.solium = keep_run("solium", r, runs),
+            Response::Mythril(r) => responses.mythril = keep_run("mythril", r, runs),
+            Response::Oyente(r) => responses.oyente = keep_run("oyente", r, runs),
+        }
+    }
+}
+
+/// Settings of an `Analysis`, see `Analysis::builder`
+#[derive(Debug)]
+pub struct AnalysisBuilder {
+    inputs: Vec<String>,
+    depth: Option<AnalysisDepth>,
+    config: Config,
+    cache: bool,
+    include_source: bool,
+    preload: bool,
+    jobs: Option<usize>,
+    keep_raw: Option<PathBuf>,
+    skip_image_check: bool,
+}
+
+// The same settings however the builder is made, results are cached unless
+// asked otherwise
+impl Default for AnalysisBuilder {
+    fn default() -> AnalysisBuilder {
+        AnalysisBuilder {
+            inputs: Vec::new(),
+            depth: None,
+            config: Config::default(),
+            cache: true,
+            include_source: false,
+            preload: false,
+            jobs: None,
+            keep_raw: None,
+            skip_image_check: false,
+        }
+    }
+}
+
+impl AnalysisBuilder {
+    /// Adds a contract to analyze
+    pub fn input<S: Into<String>>(mut self, contract_path: S) -> AnalysisBuilder {
+        self.inputs.push(contract_path.into());
+        self
+    }
+
+    pub fn inputs<I, S>(mut self, contract_paths: I) -> AnalysisBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.inputs.extend(contract_paths.into_iter().map(|p| p.into()));
+        self
+    }
+
+    /// Tools to run, all of `TOOLS` by default
+    pub fn tools<S: AsRef<str>>(mut self, tools: &[S]) -> AnalysisBuilder {
+        self.config.tools = Some(tools.iter().map(|t| t.as_ref().to_owned()).collect());
+        self
+    }
+
+    /// Tools not to run, out of those given to `tools`
+    pub fn skip_tools<S: AsRef<str>>(mut self, tools: &[S]) -> AnalysisBuilder {
+        self.config.skip_tools = tools.iter().map(|t| t.as_ref().to_owned()).collect();
+        self
+    }
+
+    pub fn depth(mut self, depth: AnalysisDepth) -> AnalysisBuilder {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Options for mythril, taking precedence over the analysis depth
+    pub fn mythril(mut self, options: MythrilOptions) -> AnalysisBuilder {
+        self.config.mythril = options;
+        self
+    }
+
+    /// Options for oyente, taking precedence over the analysis depth
+    pub fn oyente(mut self, options: OyenteOptions) -> AnalysisBuilder {
+        self.config.oyente = options;
+        self
+    }
+
+    /// Every setting of a `solsa.toml`, replacing those set so far
+    pub fn config(mut self, config: Config) -> AnalysisBuilder {
+        self.config = config;
+        self
+    }
+
+    /// `docker`, `podman` or the path to either binary, detected otherwise
+    pub fn runtime<S: Into<String>>(mut self, runtime: S) -> AnalysisBuilder {
+        self.config.runtime = Some(runtime.into());
+        self
+    }
+
+    /// Tools to run from the host `PATH` instead of their container image
+    pub fn native<S: AsRef<str>>(mut self, tools: &[S]) -> AnalysisBuilder {
+        self.config.native = tools.iter().map(|t| t.as_ref().to_owned()).collect();
+        self
+    }
+
+    pub fn sandbox(mut self, sandbox: Sandbox) -> AnalysisBuilder {
+        self.config.sandbox = sandbox;
+        self
+    }
+
+    /// Image reference a tool runs from, instead of `enhancedsociety/<tool>`
+    pub fn image<S: Into<String>>(mut self, tool: &str, reference: S) -> AnalysisBuilder {
+        self.config.images.insert(tool.to_owned(), reference.into());
+        self
+    }
+
+    /// Seconds a single tool run may take
+    pub fn timeout(mut self, seconds: u64) -> AnalysisBuilder {
+        self.config.timeout = Some(seconds);
+        self
+    }
+
+    /// Reuse results of previous runs, kept in the user's cache directory
+    pub fn cache(mut self, cache: bool) -> AnalysisBuilder {
+        self.cache = cache;
+        self
+    }
+
+    pub fn include_source(mut self, include_source: bool) -> AnalysisBuilder {
+        self.include_source = include_source;
+        self
+    }
+
+    /// Tool runs at once at most, fewer when the container runtime does not
+    /// have the memory for them. 2 by default.
+    pub fn jobs(mut self, jobs: usize) -> AnalysisBuilder {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Directory to save the raw output of each tool run in, as
+    /// `<contract>.<tool>.stdout` and `.stderr`
+    pub fn keep_raw<P: Into<PathBuf>>(mut self, dir: P) -> AnalysisBuilder {
+        self.keep_raw = Some(dir.into());
+        self
+    }
+
+    /// Whether `build` makes sure the tool images are available, it does
+    /// by default
+    pub fn verify_images(mut self, verify: bool) -> AnalysisBuilder {
+        self.skip_image_check = !verify;
+        self
+    }
+
+    /// Pull the tool images before checking they are available
+    pub fn preload(mut self, preload: bool) -> AnalysisBuilder {
+        self.preload = preload;
+        self
+    }
+
+    /// Finds the container runtime and native tools, and makes sure the
+    /// images of the tools to run are available
+    pub fn build(self) -> Result<Analysis, SolsaError> {
+        let selected = self.config.tools.as_deref().unwrap_or_default();
+        if let Some(t) = selected
+            .iter()
+            .chain(&self.config.skip_tools)
+            .find(|t| !TOOLS.contains(&t.as_str()))
+        {
+            return Err(SolsaError::config(format!("{} is not a tool solsa runs", t)));
+        }
+        // in the order of `TOOLS`, whatever the order they were given in
+        let tools = TOOLS
+            .iter()
+            .filter(|t| self.config.tools.is_none() || selected.iter().any(|s| s == *t))
+            .filter(|t| !self.config.skip_tools.iter().any(|s| s == *t))
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        if tools.is_empty() {
+            return Err(SolsaError::config("No tools left to run"));
+        }
+        if let Some(m) = self.config
+            .mythril
+            .modules
+            .iter()
+            .find(|m| !tools::MYTHRIL_MODULES.contains(&m.as_str()))
+        {
+            return Err(SolsaError::config(format!("{} is not a mythril detection module", m)));
+        }
+        let depth = self.depth.unwrap_or(AnalysisDepth::Shallow);
+        let mut backends = Backends::resolve(&self.config, &self.config.native)?;
+        backends.keep_raw = self.keep_raw;
+        let analysis = Analysis {
+            inputs: self.inputs,
+            tools,
+            depth,
+            mythril: self.config.mythril.with_depth(depth),
+            oyente: self.config.oyente.with_depth(depth),
+            include_source: self.include_source,
+            cache: if self.cache { Cache::open() } else { None },
+            backends,
+            jobs: self.jobs.unwrap_or(2).max(1),
+        };
+
+        if let (Some(ref runtime), false) = (&analysis.backends.runtime, self.skip_image_check) {
+            let needed = analysis.images();
+            if self.preload {
+                for tool in &needed {
+                    runtime.pull(tool).map_err(|e| SolsaError::Spawn {
+                        tool: runtime.name().to_owned(),
+                        message: e.to_string(),
+                    })?;
+                }
+            }
+            images::verify(runtime, &needed)?;
+        }
+        Ok(analysis)
+    }
+}
+
+/// Runs the selected tools over each input contract
+///
+/// ```no_run
+/// # extern crate solsa;
+/// # use solsa::{Analysis, AnalysisDepth, SolsaError};
+/// # fn main() -> Result<(), SolsaError> {
+/// let analysis = Analysis::builder()
+///     .input("contracts/Token.sol")
+///     .tools(&["solc", "mythril"])
+///     .depth(AnalysisDepth::Deep)
+///     .runtime("podman")
+///     .build()?;
+/// for result in analysis.run()? {
+///     for tool in &result.findings {
+///         println!("{}: {:?}, {} finding(s)", tool.tool, tool.status, tool.findings.len());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    inputs: Vec<String>,
+    tools: Vec<String>,
+    depth: AnalysisDepth,
+    mythril: MythrilOptions,
+    oyente: OyenteOptions,
+    include_source: bool,
+    cache: Option<Cache>,
+    backends: Backends,
+    jobs: usize,
+}
+
+impl Analysis {
+    pub fn builder() -> AnalysisBuilder {
+        AnalysisBuilder::default()
+    }
+
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    pub fn tools(&self) -> &[String] {
+        &self.tools
+    }
+
+    pub fn depth(&self) -> AnalysisDepth {
+        self.depth
+    }
+
+    /// Options mythril runs with, the depth's defaults filled in
+    pub fn mythril_options(&self) -> &MythrilOptions {
+        &self.mythril
+    }
+
+    /// Options oyente runs with, the depth's defaults filled in
+    pub fn oyente_options(&self) -> &OyenteOptions {
+        &self.oyente
+    }
+
+    pub fn backends(&self) -> &Backends {
+        &self.backends
+    }
+
+    pub fn cache(&self) -> Option<&Cache> {
+        self.cache.as_ref()
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    fn runs(&self, tool: &str) -> bool {
+        self.tools.iter().any(|t| t == tool)
+    }
+
+    // the flattener gives the cache key and the source in reports
+    fn needs_flattener(&self) -> bool {
+        self.include_source || self.cache.is_some()
+    }
+
+    // Images of the tools that run in a container
+    fn images(&self) -> Vec<&'static str> {
+        images::TOOLS
+            .iter()
+            .filter(|t| self.runs(t) || (**t == "solidity-flattener" && self.needs_flattener()))
+            .filter(|t| !self.backends.is_native(t))
+            .cloned()
+            .collect()
+    }
+
+    // results are cached by the flattened source, as that covers changes
+    // to any of the imported contracts too
+    fn cache_key(
+        &self,
+        contract_path: &str,
+        source: Option<&str>,
+        tool: &str,
+        args: &[String],
+    ) -> Option<CacheKey> {
+        match (&self.cache, source) {
+            (&Some(_), Some(source)) => self.backends.tool_id(tool)
+                .map(|image| CacheKey::new(tool, &image, source, contract_path, args)),
+            _ => None,
+        }
+    }
+
+    /// Flattened source of the contract, `None` when neither the cache nor
+    /// the report needs it
+    pub fn flatten(&self, contract_path: &str) -> Option<Result<String, SolsaError>> {
+        if self.needs_flattener() {
+            Some(tools::run_flattener(&self.backends, contract_path))
+        } else {
+            None
+        }
+    }
+
+    // A cached result keeps the run it came from, marked as cached
+    fn cached<T, R, S>(&self, key: Option<CacheKey>, run: R, is_success: S) -> Option<(T, ToolRun)>
+    where
+        T: Serialize + DeserializeOwned,
+        R: FnOnce() -> (T, ToolRun),
+        S: Fn(&T) -> bool,
+    {
+        let ran = Cell::new(false);
+        cache::cached(
+            self.cache.as_ref(),
+            key,
+            || {
+                ran.set(true);
+                Some(run())
+            },
+            |(r, _)| is_success(r),
+        ).map(|(r, mut tool_run)| {
+            tool_run.cached = !ran.get();
+            (r, tool_run)
+        })
+    }
+
+    fn solc(&self, contract_path: &str, source: Option<&str>) -> Option<(tools::SolcResponse, ToolRun)> {
+        self.cached(
+            self.cache_key(contract_path, source, "solc", &[]),
+            || tools::run_solc(&self.backends, contract_path),
+            |r| matches!(*r, tools::SolcResponse::Success(_)),
+        )
+    }
+
+    fn solium(&self, contract_path: &str, source: Option<&str>) -> Option<(tools::SoliumResponse, ToolRun)> {
+        self.cached(
+            self.cache_key(contract_path, source, "solium", &[]),
+            || tools::run_solium(&self.backends, contract_path),
+            |r| matches!(*r, tools::SoliumResponse::Success(_)),
+        )
+    }
+
+    fn mythril(&self, contract_path: &str, source: Option<&str>) -> Option<(tools::MythrilResponse, ToolRun)> {
+        let args = self.mythril.args();
+        self.cached(
+            self.cache_key(contract_path, source, "mythril", &args),
+            || tools::run_mythril(&self.backends, contract_path, &self.mythril),
+            |r| matches!(*r, tools::MythrilResponse::Success(ref o) if o.success),
+        )
+    }
+
+    fn oyente(&self, contract_path: &str, source: Option<&str>) -> Option<(tools::OyenteResponse, ToolRun)> {
+        let args = self.oyente.args();
+        self.cached(
+            self.cache_key(contract_path, source, "oyente", &args),
+            || tools::run_oyente(&self.backends, contract_path, &self.oyente),
+            |r| matches!(*r, tools::OyenteResponse::Success(..)),
+        )
+    }
+
+    /// Runs solc and solium, which are very fast to complete, one after the
+    /// other. Only the tools that ran are set in the responses.
+    pub fn run_fast_tools(&self, contract_path: &str, source: Option<&str>) -> ToolResponses {
+        let mut responses = ToolResponses::default();
+        if self.runs("solc") {
+            Response::Solc(self.solc(contract_path, source)).set(&mut responses);
+        }
+        if self.runs("solium") {
+            Response::Solium(self.solium(contract_path, source)).set(&mut responses);
+        }
+        responses
+    }
+
+    /// Runs mythril and oyente, which gain a bit by running in parallel as
+    /// far as `workers` allows. Only the tools that ran are set in the responses.
+    pub fn run_slow_tools(&self, contract_path: &str, source: Option<&str>) -> ToolResponses {
+        let jobs = SLOW_TOOLS
+            .iter()
+            .filter(|tool| self.runs(tool))
+            .map(|tool| Job {
+                tool: tool.to_string(),
+                contract: contract_path.to_owned(),
+            })
+            .collect();
+        let analysis = self.clone();
+        let source = source.map(|s| s.to_owned());
+        let results = scheduler::run(
+            jobs,
+            self.workers(),
+            move |job: &Job| analysis.run_job(job, source.as_deref()),
+            |_| {},
+        );
+
+        let mut responses = ToolResponses::default();
+        for (_, response) in results {
+            response.set(&mut responses);
+        }
+        responses
+    }
+
+    /// Findings of every tool, those that did not run marked as such
+    pub fn findings(&self, responses: &ToolResponses) -> Vec<ToolFindings> {
+        findings::collect(responses)
+    }
+
+    /// Tool runs at once, `jobs` or fewer if the container runtime does not
+    /// have the memory for them. Containers beyond that would just get killed.
+    pub fn workers(&self) -> usize {
+        let memory_slots = self.backends
+            .runtime
+            .as_ref()
+            .and_then(|r| r.memory())
+            .map(|m| (m / CONTAINER_MEMORY).max(1) as usize);
+        let workers = match memory_slots {
+            Some(slots) => self.jobs.min(slots),
+            None => self.jobs,
+        }.max(1);
+        debug!("running {} tool(s) at once", workers);
+        workers
+    }
+
+    fn run_job(&self, job: &Job, source: Option<&str>) -> Response {
+        let contract_path = job.contract.as_str();
+        match job.tool.as_str() {
+            "solc" => Response::Solc(self.solc(contract_path, source)),
+            "solium" => Response::Solium(self.solium(contract_path, source)),
+            "mythril" => Response::Mythril(self.mythril(contract_path, source)),
+            _ => Response::Oyente(self.oyente(contract_path, source)),
+        }
+    }
+
+    fn jobs_for(&self, inputs: &[String]) -> Vec<Job> {
+        jobs(&self.tools, inputs)
+    }
+
+    /// Every tool run `run` would go through, in order, the flattener
+    /// included when it is needed
+    pub fn plan(&self) -> Vec<Invocation> {
+        let flattener = if self.needs_flattener() {
+            self.inputs
+                .iter()
+                .map(|contract| Job {
+                    tool: "solidity-flattener".to_owned(),
+                    contract: contract.clone(),
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        flattener
+            .into_iter()
+            .chain(self.jobs_for(&self.inputs))
+            .map(|job| {
+                let command = self.backends.command(&job.tool).map(|mut cmd| {
+                    cmd.args(tools::args(&job.tool, &job.contract, &self.mythril, &self.oyente));
+                    cmd.command_line()
+                });
+                Invocation {
+                    tool: job.tool,
+                    contract: job.contract,
+                    command,
+                }
+            })
+            .collect()
+    }
+
+    fn run_inputs<P>(&self, inputs: &[String], progress: P) -> Result<Vec<ContractResult>, SolsaError>
+    where
+        P: FnMut(Progress<Job>),
+    {
+        let workers = self.workers();
+
+        let analysis = self.clone();
+        let flattened = if self.needs_flattener() {
+            scheduler::run(
+                inputs.to_vec(),
+                workers,
+                move |c: &String| analysis.flatten(c),
+                |_| {},
+            ).into_iter()
+                .map(|(_, f)| f)
+                .collect()
+        } else {
+            vec![None; inputs.len()]
+        };
+        let mut sources = Vec::new();
+        for f in &flattened {
+            sources.push(match *f {
+                // the report was asked to include the sources, so they are not optional
+                Some(ref f) if self.include_source => Some(f.clone()?),
+                _ => None,
+            });
+        }
+        // without them the results are just not cached
+        let flattened = inputs
+            .iter()
+            .cloned()
+            .zip(flattened.into_iter().map(|f| f.and_then(|f| f.ok())))
+            .collect::<HashMap<_, _>>();
+
+        let jobs = self.jobs_for(inputs);
+        let analysis = self.clone();
+        let results = scheduler::run(
+            jobs,
+            workers,
+            move |job: &Job| {
+                let source = flattened.get(&job.contract).and_then(|s| s.as_ref());
+                analysis.run_job(job, source.map(|s| s.as_str()))
+            },
+            progress,
+        );
+
+        let mut responses = inputs
+            .iter()
+            .map(|_| ToolResponses::default())
+            .collect::<Vec<_>>();
+        for (job, response) in results {
+            let i = inputs
+                .iter()
+                .position(|c| *c == job.contract)
+                .expect("Job for an unknown contract");
+            response.set(&mut responses[i]);
+        }
+        Ok(inputs
+            .iter()
+            .zip(sources)
+            .zip(responses)
+            .map(|((contract, source), responses)| ContractResult {
+                contract: contract.clone(),
+                source,
+                findings: self.findings(&responses),
+                responses,
+            })
+            .collect())
+    }
+
+    /// Runs the selected tools on one contract. Tools failing is part of the
+    /// result, an error means the contract could not be analyzed at all.
+    pub fn run_contract(&self, contract_path: &str) -> Result<ContractResult, SolsaError> {
+        let mut results = self.run_inputs(&[contract_path.to_owned()], |_| {})?;
+        Ok(results.remove(0))
+    }
+
+    pub fn run(&self) -> Result<Vec<ContractResult>, SolsaError> {
+        self.run_with_progress(|_| {})
+    }
+
+    /// Like `run`, reporting each tool run as it starts and finishes
+    pub fn run_with_progress<P>(&self, progress: P) -> Result<Vec<ContractResult>, SolsaError>
+    where
+        P: FnMut(Progress<Job>),
+    {
+        self.run_inputs(&self.inputs, progress)
+    }
+
+    // Versions are cached per image, as finding them out takes running the tool
+    fn tool_info(&self, tool: &str) -> ToolInfo {
+        let mut info = self.backends.tool_info(tool);
+        let key = self.backends
+            .tool_id(tool)
+            .map(|id| CacheKey::new(tool, &id, "", "", &["--version"]));
+        info.version = cache::cached(
+            self.cache.as_ref(),
+            key,
+            || self.backends.version(tool),
+            |_| true,
+        );
+        info
+    }
+
+    /// How each of the selected tools is run, and its version
+    pub fn tool_infos(&self) -> Vec<ToolInfo> {
+        let handles = self.tools
+            .iter()
+            .map(|tool| {
+                let analysis = self.clone();
+                let tool = tool.clone();
+                thread::spawn(move || analysis.tool_info(&tool))
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Failed to get tool versions"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builders_start_from_the_same_settings() {
+        assert!(AnalysisBuilder::default().cache);
+        assert_eq!(
+            format!("{:?}", AnalysisBuilder::default()),
+            format!("{:?}", Analysis::builder())
+        );
+    }
+
+    #[test]
+    fn fast_tools_are_scheduled_first() {
+        let tools = ["oyente", "solc", "mythril"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        let inputs = vec!["A.sol".to_owned(), "B.sol".to_owned()];
+        let order = jobs(&tools, &inputs)
+            .into_iter()
+            .map(|j| format!("{} {}", j.tool, j.contract))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![
+                "solc A.sol",
+                "solc B.sol",
+                "mythril A.sol",
+                "mythril B.sol",
+                "oyente A.sol",
+                "oyente B.sol",
+            ]
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::io::Read;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::Level;
+
+use config::Config;
+use docker::Runtime;
+use error::SolsaError;
+
+// Tools that can run from the host instead of a container, oyente depends on
+// a patched fork that is only shipped in its image
+pub const NATIVE_TOOLS: &[&str] = &["solc", "solium", "mythril", "solidity-flattener"];
+
+fn binary_name(tool: &str) -> &str {
+    match tool {
+        "mythril" => "myth",
+        "solidity-flattener" => "solidity_flattener",
+        t => t,
+    }
+}
+
+// major, minor, patch
+type Version = (u32, u32, u32);
+
+// Oldest supported version and first unsupported one, for the versions whose
+// output the parsers in `tools.rs` understand
+fn compatible_versions(tool: &str) -> Option<(Version, Version)> {
+    match tool {
+        // solc 0.8 reports the abi as json instead of a string
+        "solc" => Some(((0, 4, 11), (0, 8, 0))),
+        // gcc reporter
+        "solium" => Some(((1, 0, 0), (2, 0, 0))),
+        // `-x` and `--max-depth`, and the json output format with `success`
+        "mythril" => Some(((0, 18, 0), (0, 19, 0))),
+        _ => None,
+    }
+}
+
+// first x.y.z in the output, e.g. `Version: 0.4.24+commit.e67f0147.Linux.g++`
+// or `Mythril version v0.18.12`
+fn parse_version(output: &str) -> Option<Version> {
+    output
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter_map(|token| {
+            let parts = token
+                .split('.')
+                .map(|p| p.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>()?;
+            if parts.len() >= 3 {
+                Some((parts[0], parts[1], parts[2]))
+            } else {
+                None
+            }
+        })
+        .next()
+}
+
+#[derive(Clone, Debug)]
+pub struct NativeTool {
+    pub binary: PathBuf,
+    pub version: String,
+}
+
+impl NativeTool {
+    // Looks the tool up on `PATH` and makes sure its version is one solsa
+    // knows how to run
+    pub fn find(tool: &str) -> Result<NativeTool, String> {
+        let name = binary_name(tool);
+        let binary = env::var_os("PATH")
+            .and_then(|paths| {
+                env::split_paths(&paths)
+                    .map(|p| p.join(name))
+                    .find(|p| p.is_file())
+            })
+            .ok_or_else(|| format!("{} could not be found on PATH", name))?;
+
+        let output = Command::new(&binary)
+            .arg("--version")
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", binary.display(), e))?;
+        let reported = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let version = checked_version(tool, &reported)?;
+        Ok(NativeTool { binary, version })
+    }
+}
+
+// The version `--version` reported, when it is one solsa knows how to run
+fn checked_version(tool: &str, reported: &str) -> Result<String, String> {
+    let name = binary_name(tool);
+    match compatible_versions(tool) {
+        Some((min, max)) => {
+            let v = parse_version(reported).ok_or_else(|| {
+                format!("Could not tell which version of {} is installed", name)
+            })?;
+            if v < min || v >= max {
+                return Err(format!(
+                    "{} {}.{}.{} is not supported, solsa needs >= {}.{}.{} and < {}.{}.{}",
+                    name, v.0, v.1, v.2, min.0, min.1, min.2, max.0, max.1, max.2
+                ));
+            }
+            Ok(format!("{}.{}.{}", v.0, v.1, v.2))
+        }
+        None => Ok(reported.trim().to_owned()),
+    }
+}
+
+// How a tool was run, recorded in reports so results can be reproduced
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolInfo {
+    pub tool: String,
+    // `docker`, `podman`, `native` or `none` when no runtime was available
+    pub backend: String,
+    // image reference, or the binary of native tools
+    pub image: Option<String>,
+    pub digest: Option<String>,
+    pub version: Option<String>,
+}
+
+// Quoted as a shell would need it, so a logged command can be pasted back
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars()
+            .all(|c| c.is_alphanumeric() || "-_./:=,@%+".contains(c));
+    if plain {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn command_line(command: &Command) -> String {
+    let mut words = vec![shell_quote(&command.get_program().to_string_lossy())];
+    words.extend(command.get_args().map(|a| shell_quote(&a.to_string_lossy())));
+    words.join(" ")
+}
+
+fn read_all<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut p) = pipe {
+            let _ = p.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+// A tool invocation, killed (along with its container) when it runs longer
+// than the timeout
+pub struct ToolCommand {
+    tool: String,
+    command: Command,
+    container: Option<(Runtime, String)>,
+    timeout: Option<u64>,
+}
+
+impl Deref for ToolCommand {
+    type Target = Command;
+
+    fn deref(&self) -> &Command {
+        &self.command
+    }
+}
+
+impl DerefMut for ToolCommand {
+    fn deref_mut(&mut self) -> &mut Command {
+        &mut self.command
+    }
+}
+
+impl ToolCommand {
+    /// The command line, quoted so it can be pasted into a shell
+    pub fn command_line(&self) -> String {
+        command_line(&self.command)
+    }
+
+    pub fn output(&mut self) -> Result<Output, SolsaError> {
+        let tool = self.tool.clone();
+        let cmdline = command_line(&self.command);
+        info!(tool = tool.as_str(), command = cmdline.as_str(); "{}: {}", tool, cmdline);
+        if log_enabled!(Level::Debug) {
+            let cwd = match self.command.get_current_dir() {
+                Some(dir) => dir.display().to_string(),
+                None => env::current_dir()
+                    .map(|d| d.display().to_string())
+                    .unwrap_or_default(),
+            };
+            let environment = self.command
+                .get_envs()
+                .map(|(k, v)| match v {
+                    Some(v) => format!("{}={}", k.to_string_lossy(), shell_quote(&v.to_string_lossy())),
+                    None => format!("-{}", k.to_string_lossy()),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            debug!(
+                tool = tool.as_str(), cwd = cwd.as_str(), env = environment.as_str();
+                "{}: working directory {}, environment {}",
+                tool,
+                cwd,
+                if environment.is_empty() { "inherited" } else { environment.as_str() }
+            );
+        }
+        let started = Instant::now();
+        let mut child = self.command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| SolsaError::Spawn {
+                tool: tool.clone(),
+                message: e.to_string(),
+            })?;
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+
+        // the timeout, and when it is up
+        let deadline = self.timeout
+            .map(|secs| (secs, Instant::now() + Duration::from_secs(secs)));
+        let status = loop {
+            let waited = match deadline {
+                Some(_) => child.try_wait(),
+                None => child.wait().map(Some),
+            };
+            match (waited, deadline) {
+                (Ok(Some(status)), _) => break status,
+                (Ok(None), Some((seconds, at))) if Instant::now() >= at => {
+                    let _ = child.kill();
+                    if let Some((ref runtime, ref name)) = self.container {
+                        runtime.kill(name);
+                    }
+                    let _ = child.wait();
+                    warn!(tool = tool.as_str(); "{}: stopped after {}s", tool, seconds);
+                    return Err(SolsaError::Timeout { tool, seconds });
+                }
+                (Ok(None), _) => thread::sleep(Duration::from_millis(100)),
+                (Err(e), _) => {
+                    return Err(SolsaError::Spawn {
+                        tool,
+                        message: e.to_string(),
+                    })
+                }
+            }
+        };
+
+        let output = Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+        let elapsed = started.elapsed();
+        info!(
+            tool = tool.as_str(),
+            status = output.status.code().map(i64::from).unwrap_or(-1),
+            seconds = elapsed.as_secs_f64(),
+            stdout_bytes = output.stdout.len(),
+            stderr_bytes = output.stderr.len();
+            "{}: {} in {:.1}s, {} bytes on stdout, {} bytes on stderr",
+            tool,
+            match output.status.code() {
+                Some(code) => format!("exited with status {}", code),
+                None => "killed by a signal".to_owned(),
+            },
+            elapsed.as_secs_f64(),
+            output.stdout.len(),
+            output.stderr.len()
+        );
+        Ok(output)
+    }
+}
+
+// Decides, per tool, whether it runs from its container image or natively
+#[derive(Clone, Debug)]
+pub struct Backends {
+    pub runtime: Option<Runtime>,
+    pub native: BTreeMap<String, NativeTool>,
+    // seconds a single tool run may take
+    pub timeout: Option<u64>,
+    // directory each tool's raw output is saved in
+    pub keep_raw: Option<PathBuf>,
+}
+
+impl Backends {
+    // Tools asked to run natively are looked up on the host, the rest need a
+    // container runtime, which is only optional when something can run without it
+    pub fn resolve(config: &Config, native: &[String]) -> Result<Backends, SolsaError> {
+        let native = native
+            .iter()
+            .map(|tool| {
+                if !NATIVE_TOOLS.contains(&tool.as_str()) {
+                    return Err(SolsaError::config(format!("{} can not run natively", tool)));
+                }
+                let n = NativeTool::find(tool).map_err(SolsaError::config)?;
+                Ok((tool.clone(), n))
+            })
+            .collect::<Result<BTreeMap<_, _>, SolsaError>>()?;
+        let runtime = match Runtime::detect(config.runtime.as_deref()) {
+            Ok(mut r) => {
+                r.sandbox = config.sandbox.clone();
+                r.images = config.images.clone();
+                Some(r)
+            }
+            Err(ref e) if !native.is_empty() => {
+                warn!("{}, tools that run in containers will be skipped", e);
+                None
+            }
+            Err(e) => {
+                return Err(SolsaError::Spawn {
+                    tool: "container runtime".to_owned(),
+                    message: format!("{}, run `solsa doctor` for details", e),
+                })
+            }
+        };
+        Ok(Backends {
+            runtime,
+            native,
+            timeout: config.timeout,
+            keep_raw: None,
+        })
+    }
+
+    pub fn is_native(&self, tool: &str) -> bool {
+        self.native.contains_key(tool)
+    }
+
+    pub fn command(&self, tool: &str) -> Result<ToolCommand, SolsaError> {
+        let (command, container) = match (self.native.get(tool), self.runtime.as_ref()) {
+            (Some(n), _) => (Command::new(&n.binary), None),
+            (None, Some(r)) => {
+                let (command, name) = r.run_cmd(tool)?;
+                (command, Some((r.clone(), name)))
+            }
+            (None, None) => {
+                return Err(SolsaError::Spawn {
+                    tool: tool.to_owned(),
+                    message: "no container runtime is available".to_owned(),
+                })
+            }
+        };
+        Ok(ToolCommand {
+            tool: tool.to_owned(),
+            command,
+            container,
+            timeout: self.timeout,
+        })
+    }
+
+    // Identifies exactly what a tool runs from, for the results cache
+    pub fn tool_id(&self, tool: &str) -> Option<String> {
+        match self.native.get(tool) {
+            Some(n) => Some(format!("native:{}@{}", n.binary.display(), n.version)),
+            None => self
+                .runtime
+                .as_ref()
+                .and_then(|r| r.image_info(tool))
+                .map(|i| i.id),
+        }
+    }
+
+    // What the tool says its version is, the last line is the one with the
+    // version for all of them (solc prints a banner first)
+    pub fn version(&self, tool: &str) -> Option<String> {
+        if let Some(n) = self.native.get(tool) {
+            return Some(n.version.clone());
+        }
+        let mut cmd = self.command(tool).ok()?;
+        cmd.arg("--version");
+        let output = cmd.output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8(output.stdout)
+            .ok()?
+            .lines()
+            .map(|l| l.trim())
+            .rfind(|l| !l.is_empty())
+            .map(|l| l.to_owned())
+    }
+
+    // Without the version, which takes running the tool
+    pub fn tool_info(&self, tool: &str) -> ToolInfo {
+        let (backend, image, digest) = match (self.native.get(tool), self.runtime.as_ref()) {
+            (Some(n), _) => ("native", Some(n.binary.display().to_string()), None),
+            (None, Some(r)) => (
+                r.name(),
+                Some(r.image(tool)),
+                r.image_info(tool).and_then(|i| i.digest),
+            ),
+            (None, None) => ("none", None, None),
+        };
+        ToolInfo {
+            tool: tool.to_owned(),
+            backend: backend.to_owned(),
+            image,
+            digest,
+            version: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLC: &str = "solc, the solidity compiler commandline interface\n\
+                        Version: 0.4.24+commit.e67f0147.Linux.g++\n";
+
+    #[test]
+    fn parses_solc_and_mythril_versions() {
+        assert_eq!(parse_version(SOLC), Some((0, 4, 24)));
+        assert_eq!(parse_version("Mythril version v0.18.12\n"), Some((0, 18, 12)));
+    }
+
+    #[test]
+    fn unparseable_version_is_rejected() {
+        assert_eq!(parse_version("solc, version unknown"), None);
+        assert_eq!(parse_version("Version: 0.4"), None);
+        assert!(checked_version("solc", "solc, version unknown").is_err());
+    }
+
+    #[test]
+    fn supported_versions_are_accepted() {
+        assert_eq!(checked_version("solc", SOLC), Ok("0.4.24".to_owned()));
+        assert_eq!(
+            checked_version("mythril", "Mythril version v0.18.12"),
+            Ok("0.18.12".to_owned())
+        );
+    }
+
+    #[test]
+    fn other_major_or_minor_versions_are_rejected() {
+        assert!(checked_version("solc", "Version: 0.8.0+commit.c7dfd78e").is_err());
+        assert!(checked_version("solc", "Version: 0.3.6").is_err());
+        assert!(checked_version("solium", "2.0.1").is_err());
+        assert!(checked_version("mythril", "Mythril version v0.19.0").is_err());
+        assert!(checked_version("mythril", "Mythril version v0.17.9").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+
+use findings::fnv1a;
+
+// Everything that can change a tool's results has to be part of the key.
+// The source is kept whole, as contracts are untrusted and a hash of it could
+// be collided on purpose to pass off another contract's results.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct CacheKey {
+    pub tool: String,
+    pub image: String,
+    pub source: String,
+    pub contract_path: String,
+    pub args: Vec<String>,
+    pub solsa_version: String,
+}
+
+impl CacheKey {
+    pub fn new<S: AsRef<str>>(
+        tool: &str,
+        image: &str,
+        source: &str,
+        contract_path: &str,
+        args: &[S],
+    ) -> CacheKey {
+        CacheKey {
+            tool: tool.to_owned(),
+            image: image.to_owned(),
+            source: source.to_owned(),
+            contract_path: contract_path.to_owned(),
+            args: args.iter().map(|a| a.as_ref().to_owned()).collect(),
+            solsa_version: crate_version!().to_owned(),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        let key = serde_json::to_string(self).expect("Failed to serialize cache key");
+        format!("{}-{:016x}.json", self.tool, fnv1a(key.as_bytes()))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<T> {
+    key: CacheKey,
+    response: T,
+}
+
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    // follows the XDG base directory spec, i.e. ~/.cache/solsa by default
+    pub fn open() -> Option<Cache> {
+        env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+            .map(|d| Cache {
+                dir: d.join("solsa"),
+            })
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        fs::read_to_string(self.dir.join(key.file_name()))
+            .ok()
+            .and_then(|s| serde_json::from_str::<CacheEntry<T>>(&s).ok())
+            .and_then(|e| {
+                // entries are found by a hash of the key, only the whole key
+                // tells it is the same one
+                if e.key == *key {
+                    Some(e.response)
+                } else {
+                    None
+                }
+            })
+    }
+
+    // failing to cache is not worth failing the analysis for
+    pub fn put<T: Serialize>(&self, key: CacheKey, response: &T) {
+        let path = self.dir.join(key.file_name());
+        let entry = CacheEntry { key, response };
+        if let Ok(s) = serde_json::to_string(&entry) {
+            let _ = fs::create_dir_all(&self.dir).and_then(|_| fs::write(&path, &s));
+        }
+    }
+
+    pub fn clean(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => r,
+        }
+    }
+}
+
+// Only successful responses are cached, failures may well be transient
+pub fn cached<T, R, S>(cache: Option<&Cache>, key: Option<CacheKey>, run: R, is_success: S) -> Option<T>
+where
+    T: Serialize + DeserializeOwned,
+    R: FnOnce() -> Option<T>,
+    S: Fn(&T) -> bool,
+{
+    match (cache, key) {
+        (Some(c), Some(k)) => match c.get(&k) {
+            Some(r) => Some(r),
+            None => {
+                let r = run();
+                if let Some(ref response) = r {
+                    if is_success(response) {
+                        c.put(k, response);
+                    }
+                }
+                r
+            }
+        },
+        _ => run(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_of_another_source_is_not_used() {
+        let cache = Cache {
+            dir: env::temp_dir().join(format!("solsa-cache-test-{}", std::process::id())),
+        };
+        let honest = CacheKey::new("mythril", "image", "contract A {}", "A.sol", &["--max-depth", "4"]);
+        let crafted = CacheKey::new("mythril", "image", "contract B {}", "A.sol", &["--max-depth", "4"]);
+        cache.put(honest, &"no issues".to_owned());
+        // as if the two keys' file names collided
+        let honest = CacheKey::new("mythril", "image", "contract A {}", "A.sol", &["--max-depth", "4"]);
+        fs::rename(cache.dir.join(honest.file_name()), cache.dir.join(crafted.file_name()))
+            .expect("Failed to move cache entry");
+        assert_eq!(cache.get::<String>(&crafted), None);
+        cache.clean().expect("Failed to clean test cache");
+    }
+}
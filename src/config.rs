@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use toml;
+
+use docker::Sandbox;
+use error::SolsaError;
+use tools::{MythrilOptions, OyenteOptions};
+
+const DEFAULT_CONFIG_FILE: &str = "solsa.toml";
+
+// Settings that would otherwise have to be repeated on every invocation,
+// read from `solsa.toml` in the current directory unless a file is given
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // `docker`, `podman` or the path to either binary
+    pub runtime: Option<String>,
+    // tools to run, all of them when not set
+    pub tools: Option<Vec<String>>,
+    // tools not to run, e.g. one known to crash on the contracts
+    pub skip_tools: Vec<String>,
+    // tools to run from the host PATH instead of their container image
+    pub native: Vec<String>,
+    pub sandbox: Sandbox,
+    // tool -> image reference, to pin tools by tag or digest
+    pub images: BTreeMap<String, String>,
+    // seconds a single tool run may take before it is killed
+    pub timeout: Option<u64>,
+    // with --error-exit, whether tools that failed to run still let it pass
+    pub ignore_tool_errors: bool,
+    // override what the analysis depth sets, and the tools' own defaults
+    pub mythril: MythrilOptions,
+    pub oyente: OyenteOptions,
+}
+
+impl Config {
+    /// The file `load` reads, `None` when the defaults are used
+    pub fn path(path: Option<&str>) -> Option<&str> {
+        match path {
+            Some(p) => Some(p),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Some(DEFAULT_CONFIG_FILE),
+            None => None,
+        }
+    }
+
+    pub fn load(path: Option<&str>) -> Result<Config, SolsaError> {
+        let path = match Config::path(path) {
+            Some(p) => p,
+            None => return Ok(Config::default()),
+        };
+        let s = fs::read_to_string(path).map_err(|e| SolsaError::io(path, &e))?;
+        toml::from_str(&s)
+            .map_err(|e| SolsaError::config(format!("Invalid config file {}: {}", path, e)))
+    }
+
+    /// The settings in effect, as they would be written in a `solsa.toml`
+    pub fn to_toml(&self) -> String {
+        // through a `Value`, which puts tables after plain values as toml requires
+        toml::Value::try_from(self)
+            .and_then(|v| toml::to_string(&v))
+            .expect("Failed to serialize config")
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use findings::{fingerprints, Finding, ToolFindings, ToolStatus};
+
+#[derive(Serialize, Debug, Default)]
+pub struct ContractDiff {
+    pub contract: String,
+    pub new: Vec<Finding>,
+    pub fixed: Vec<Finding>,
+    pub unchanged: Vec<Finding>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ToolDiff {
+    pub tool: String,
+    pub old_status: Option<ToolStatus>,
+    pub new_status: Option<ToolStatus>,
+    /// The tool completed in both reports (or is absent from one of them),
+    /// otherwise its findings are left out rather than reported as fixed or new
+    pub comparable: bool,
+    pub contracts: Vec<ContractDiff>,
+}
+
+// A failed or skipped run says nothing of the findings it would have had
+fn completed(tool: Option<&ToolFindings>) -> bool {
+    match tool {
+        Some(t) => t.status == ToolStatus::Ok || t.status == ToolStatus::Issues,
+        None => true,
+    }
+}
+
+fn keyed(tool: Option<&ToolFindings>) -> HashMap<String, &Finding> {
+    match tool {
+        Some(t) => {
+            let mut findings = t.findings.iter().collect::<Vec<_>>();
+            findings.sort_by_key(|f| (f.filename.clone(), f.line, f.column));
+            fingerprints(findings.iter().cloned())
+                .into_iter()
+                .zip(findings)
+                .collect()
+        }
+        None => HashMap::new(),
+    }
+}
+
+fn contract_entry<'a>(
+    contracts: &'a mut BTreeMap<String, ContractDiff>,
+    f: &Finding,
+) -> &'a mut ContractDiff {
+    contracts
+        .entry(f.contract.clone())
+        .or_insert_with(|| ContractDiff {
+            contract: f.contract.clone(),
+            ..Default::default()
+        })
+}
+
+// Findings are matched by fingerprint, so moving code around does not turn
+// them into new/fixed ones
+pub fn diff(old: &[ToolFindings], new: &[ToolFindings]) -> Vec<ToolDiff> {
+    let mut tools = old.iter().map(|t| t.tool.clone()).collect::<Vec<_>>();
+    for t in new {
+        if !tools.contains(&t.tool) {
+            tools.push(t.tool.clone());
+        }
+    }
+
+    tools
+        .into_iter()
+        .map(|tool| {
+            let old_tool = old.iter().find(|t| t.tool == tool);
+            let new_tool = new.iter().find(|t| t.tool == tool);
+            let comparable = completed(old_tool) && completed(new_tool);
+            let (old_findings, new_findings) = if comparable {
+                (keyed(old_tool), keyed(new_tool))
+            } else {
+                (HashMap::new(), HashMap::new())
+            };
+
+            let mut contracts = BTreeMap::new();
+            for (fp, f) in &new_findings {
+                if old_findings.contains_key(fp) {
+                    contract_entry(&mut contracts, f).unchanged.push((*f).clone());
+                } else {
+                    contract_entry(&mut contracts, f).new.push((*f).clone());
+                }
+            }
+            for (fp, f) in &old_findings {
+                if !new_findings.contains_key(fp) {
+                    contract_entry(&mut contracts, f).fixed.push((*f).clone());
+                }
+            }
+            for c in contracts.values_mut() {
+                for l in &mut [&mut c.new, &mut c.fixed, &mut c.unchanged] {
+                    l.sort_by_key(|f| (f.filename.clone(), f.line, f.column));
+                }
+            }
+
+            ToolDiff {
+                tool,
+                old_status: old_tool.map(|t| t.status),
+                new_status: new_tool.map(|t| t.status),
+                comparable,
+                contracts: contracts.into_values().collect(),
+            }
+        })
+        .collect()
+}
+
+// The status that says the most about a tool across several contracts
+fn combined(a: Option<ToolStatus>, b: Option<ToolStatus>) -> Option<ToolStatus> {
+    let rank = |s: Option<ToolStatus>| match s {
+        None => 0,
+        Some(ToolStatus::NotRun) => 1,
+        Some(ToolStatus::Ok) => 2,
+        Some(ToolStatus::Issues) => 3,
+        Some(ToolStatus::Failed) => 4,
+    };
+    if rank(b) > rank(a) {
+        b
+    } else {
+        a
+    }
+}
+
+/// Diffs reports of several contracts (as written by `--json` for more than
+/// one), pairing them by contract file. A single report on each side is
+/// diffed whatever its file was called, so a renamed contract still matches.
+pub fn diff_contracts(
+    old: &[(String, Vec<ToolFindings>)],
+    new: &[(String, Vec<ToolFindings>)],
+) -> Vec<ToolDiff> {
+    if old.len() == 1 && new.len() == 1 {
+        return diff(&old[0].1, &new[0].1);
+    }
+    let findings = |reports: &[(String, Vec<ToolFindings>)], file: &str| {
+        reports
+            .iter()
+            .find(|r| r.0 == file)
+            .map(|r| r.1.clone())
+            .unwrap_or_default()
+    };
+    let files = old
+        .iter()
+        .chain(new)
+        .map(|r| r.0.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let mut tools: Vec<ToolDiff> = Vec::new();
+    for file in files {
+        for tool_diff in diff(&findings(old, file), &findings(new, file)) {
+            match tools.iter_mut().find(|t| t.tool == tool_diff.tool) {
+                Some(t) => {
+                    t.old_status = combined(t.old_status, tool_diff.old_status);
+                    t.new_status = combined(t.new_status, tool_diff.new_status);
+                    t.comparable = t.comparable && tool_diff.comparable;
+                    t.contracts.extend(tool_diff.contracts);
+                }
+                None => tools.push(tool_diff),
+            }
+        }
+    }
+    tools
+}
+
+/// Number of new, fixed and unchanged findings across all tools
+pub fn totals(diff: &[ToolDiff]) -> (usize, usize, usize) {
+    diff.iter()
+        .flat_map(|t| t.contracts.iter())
+        .fold((0, 0, 0), |(new, fixed, unchanged), c| {
+            (
+                new + c.new.len(),
+                fixed + c.fixed.len(),
+                unchanged + c.unchanged.len(),
+            )
+        })
+}
+
+fn status_label(status: Option<ToolStatus>) -> &'static str {
+    match status {
+        Some(ToolStatus::Ok) => "ok",
+        Some(ToolStatus::Issues) => "issues",
+        Some(ToolStatus::Failed) => "failed",
+        Some(ToolStatus::NotRun) => "not run",
+        None => "absent",
+    }
+}
+
+pub fn render_text(diff: &[ToolDiff], color: bool, include_unchanged: bool) -> String {
+    let paint = |code: &str, s: String| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", code, s)
+        } else {
+            s
+        }
+    };
+    let line = |f: &Finding| {
+        format!(
+            "{}:{}:{}: [{}/{}] {}",
+            f.filename,
+            f.line,
+            f.column,
+            f.tool,
+            f.rule,
+            f.message.split_whitespace().collect::<Vec<_>>().join(" ")
+        )
+    };
+
+    let mut out = String::new();
+    let (new, fixed, unchanged) = totals(diff);
+    for t in diff {
+        let changed = t.old_status != t.new_status
+            || t.contracts
+                .iter()
+                .any(|c| !c.new.is_empty() || !c.fixed.is_empty());
+        if !include_unchanged && !changed {
+            continue;
+        }
+        out.push_str(&paint("1", t.tool.clone()));
+        if t.old_status != t.new_status {
+            out.push_str(&format!(
+                " ({} -> {})",
+                status_label(t.old_status),
+                status_label(t.new_status)
+            ));
+        }
+        if !t.comparable {
+            out.push_str(", findings not compared as it did not complete in both reports");
+        }
+        out.push('\n');
+        for c in &t.contracts {
+            if !include_unchanged && c.new.is_empty() && c.fixed.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("  {}\n", c.contract));
+            for f in &c.new {
+                out.push_str(&paint("31", format!("    + {}", line(f))));
+                out.push('\n');
+            }
+            for f in &c.fixed {
+                out.push_str(&paint("32", format!("    - {}", line(f))));
+                out.push('\n');
+            }
+            if include_unchanged {
+                for f in &c.unchanged {
+                    out.push_str(&format!("    = {}\n", line(f)));
+                }
+            }
+        }
+    }
+    out.push_str(&format!(
+        "\n{} new, {} fixed, {} unchanged\n",
+        paint("31", new.to_string()),
+        paint("32", fixed.to_string()),
+        unchanged
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::SolsaError;
+    use findings::finding;
+
+    fn tool(name: &str, findings: Vec<Finding>) -> ToolFindings {
+        ToolFindings {
+            tool: name.to_owned(),
+            status: if findings.is_empty() {
+                ToolStatus::Ok
+            } else {
+                ToolStatus::Issues
+            },
+            findings,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn moved_finding_is_unchanged() {
+        let old = vec![tool("solium", vec![finding("A.sol", 3, "Avoid using 'now'.")])];
+        let new = vec![tool("solium", vec![finding("A.sol", 12, "Avoid using 'now'.")])];
+        assert_eq!(totals(&diff(&old, &new)), (0, 0, 1));
+    }
+
+    #[test]
+    fn added_duplicate_is_new() {
+        let old = vec![tool("solium", vec![finding("A.sol", 3, "Avoid using 'now'.")])];
+        let new = vec![tool(
+            "solium",
+            vec![finding("A.sol", 3, "Avoid using 'now'."), finding("A.sol", 9, "Avoid using 'now'.")],
+        )];
+        let d = diff(&old, &new);
+        assert_eq!(totals(&d), (1, 0, 1));
+        assert_eq!(d[0].contracts[0].new[0].line, 9);
+    }
+
+    #[test]
+    fn removed_duplicate_is_fixed() {
+        let old = vec![tool(
+            "solium",
+            vec![finding("A.sol", 3, "Avoid using 'now'."), finding("A.sol", 9, "Avoid using 'now'.")],
+        )];
+        let new = vec![tool("solium", vec![finding("A.sol", 5, "Avoid using 'now'.")])];
+        assert_eq!(totals(&diff(&old, &new)), (0, 1, 1));
+    }
+
+    #[test]
+    fn tool_in_one_report_only() {
+        let old = vec![tool("solium", vec![])];
+        let new = vec![
+            tool("solium", vec![]),
+            tool("mythril", vec![Finding {
+                tool: "mythril".to_owned(),
+                ..finding("A.sol", 6, "A reachable exception has been detected.")
+            }]),
+        ];
+        let d = diff(&old, &new);
+        let mythril = d.iter().find(|t| t.tool == "mythril").expect("mythril is diffed");
+        assert_eq!(mythril.old_status, None);
+        assert_eq!(mythril.new_status, Some(ToolStatus::Issues));
+        assert_eq!(totals(&d), (1, 0, 0));
+
+        let d = diff(&new, &old);
+        let mythril = d.iter().find(|t| t.tool == "mythril").expect("mythril is diffed");
+        assert_eq!(mythril.new_status, None);
+        assert_eq!(totals(&d), (0, 1, 0));
+    }
+
+    #[test]
+    fn contracts_are_paired_by_file() {
+        let b = |line| finding("B.sol", line, "Avoid using 'now'.");
+        let old = vec![
+            ("A.sol".to_owned(), vec![tool("solium", vec![finding("A.sol", 3, "Avoid using 'now'.")])]),
+            ("B.sol".to_owned(), vec![tool("solium", vec![b(4)])]),
+        ];
+        let new = vec![
+            ("B.sol".to_owned(), vec![tool("solium", vec![b(8)])]),
+            ("C.sol".to_owned(), vec![tool("solium", vec![])]),
+        ];
+        let d = diff_contracts(&old, &new);
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].old_status, Some(ToolStatus::Issues));
+        assert_eq!(d[0].new_status, Some(ToolStatus::Issues));
+        assert_eq!(totals(&d), (0, 1, 1));
+    }
+
+    #[test]
+    fn timed_out_tool_is_not_compared() {
+        let old = vec![
+            tool("solium", vec![finding("A.sol", 3, "Avoid using 'now'.")]),
+            tool("mythril", vec![Finding {
+                tool: "mythril".to_owned(),
+                ..finding("A.sol", 6, "A reachable exception has been detected.")
+            }]),
+        ];
+        let new = vec![
+            tool("solium", vec![finding("A.sol", 3, "Avoid using 'now'.")]),
+            ToolFindings {
+                tool: "mythril".to_owned(),
+                status: ToolStatus::Failed,
+                findings: vec![],
+                error: Some(SolsaError::Timeout {
+                    tool: "mythril".to_owned(),
+                    seconds: 600,
+                }),
+            },
+        ];
+        let d = diff(&old, &new);
+        let mythril = d.iter().find(|t| t.tool == "mythril").expect("mythril is diffed");
+        assert!(!mythril.comparable);
+        assert!(mythril.contracts.is_empty());
+        assert_eq!(totals(&d), (0, 0, 1));
+    }
+}
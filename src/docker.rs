@@ -1,13 +1,320 @@
-macro_rules! docker_cmd {
-    ($e:expr) => {{
-        let mut dc = Command::new("docker");
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use error::SolsaError;
+
+pub const RUNTIME_ENV: &str = "SOLSA_CONTAINER_RUNTIME";
+
+// the `-m` every container runs with, in bytes
+pub const CONTAINER_MEMORY: u64 = 1536 * 1024 * 1024;
+
+// Tells apart the containers of one solsa process, so they can be killed by name
+static CONTAINERS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    // `uid:gid` of the user running solsa
+    static ref HOST_USER: Option<(String, String)> = {
+        let id = |flag: &str| {
+            Command::new("id")
+                .arg(flag)
+                .output()
+                .ok()
+                .and_then(|o| String::from_utf8(o.stdout).ok())
+                .map(|s| s.trim().to_owned())
+        };
+        match (id("-u"), id("-g")) {
+            (Some(u), Some(g)) => Some((u, g)),
+            _ => None,
+        }
+    };
+}
+
+pub fn host_uid() -> Option<String> {
+    HOST_USER.as_ref().map(|(uid, _)| uid.clone())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuntimeKind {
+    Docker,
+    Podman,
+}
+
+// Restrictions for tool containers, as contracts (and the tools' handling
+// of them) are not necessarily trusted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sandbox {
+    pub enabled: bool,
+    pub pids_limit: u32,
+    pub cpus: f64,
+    // `uid:gid` to run tools as, the host user when not set
+    pub user: Option<String>,
+}
+
+impl Default for Sandbox {
+    fn default() -> Sandbox {
+        Sandbox {
+            enabled: true,
+            pids_limit: 256,
+            cpus: 2.0,
+            user: None,
+        }
+    }
+}
+
+// What an image reference resolved to locally
+#[derive(Clone, Debug)]
+pub struct ImageInfo {
+    pub id: String,
+    pub digest: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Runtime {
+    pub kind: RuntimeKind,
+    pub binary: PathBuf,
+    pub sandbox: Sandbox,
+    // tool -> image reference, pinned by tag or digest, e.g.
+    // `enhancedsociety/solc@sha256:...`
+    pub images: BTreeMap<String, String>,
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|p| p.join(name))
+            .find(|p| p.is_file())
+    })
+}
+
+// `docker` may well be podman's docker compatible wrapper, so ask the binary
+// rather than trusting its name
+fn probe_kind(binary: &Path) -> RuntimeKind {
+    let reported = Command::new(binary)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let name = binary
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if reported.contains("podman") || (reported.is_empty() && name.contains("podman")) {
+        RuntimeKind::Podman
+    } else {
+        RuntimeKind::Docker
+    }
+}
+
+impl Runtime {
+    // `docker`, `podman` or an explicit path to a runtime binary
+    pub fn from_name(name: &str) -> Option<Runtime> {
+        let binary = if name.contains('/') {
+            Some(PathBuf::from(name)).filter(|p| p.is_file())
+        } else {
+            find_on_path(name)
+        };
+        binary.map(|binary| Runtime {
+            kind: probe_kind(&binary),
+            binary,
+            sandbox: Sandbox::default(),
+            images: BTreeMap::new(),
+        })
+    }
+
+    // The env var takes precedence over the configured runtime, without
+    // either docker is preferred over podman
+    pub fn detect(configured: Option<&str>) -> Result<Runtime, String> {
+        let preferred = env::var(RUNTIME_ENV)
+            .ok()
+            .filter(|r| !r.is_empty())
+            .or_else(|| configured.map(|r| r.to_owned()));
+        match preferred {
+            Some(name) => Runtime::from_name(&name)
+                .ok_or_else(|| format!("Container runtime {} could not be found", name)),
+            None => Runtime::from_name("docker")
+                .or_else(|| Runtime::from_name("podman"))
+                .ok_or_else(|| {
+                    "Neither docker nor podman seem to be installed, one is required".to_owned()
+                }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            RuntimeKind::Docker => "docker",
+            RuntimeKind::Podman => "podman",
+        }
+    }
+
+    pub fn command(&self) -> Command {
+        Command::new(&self.binary)
+    }
+
+    // Unless the manifest says otherwise tools run from the `latest` image,
+    // podman does not assume docker hub for unqualified references, and may
+    // prompt for a registry when it is not configured
+    pub fn image(&self, tool: &str) -> String {
+        let reference = self
+            .images
+            .get(tool)
+            .cloned()
+            .unwrap_or_else(|| format!("enhancedsociety/{}", tool));
+        let qualified = reference
+            .split('/')
+            .next()
+            .map(|registry| {
+                reference.contains('/')
+                    && (registry.contains('.') || registry.contains(':') || registry == "localhost")
+            })
+            .unwrap_or(false);
+        match self.kind {
+            RuntimeKind::Podman if !qualified => format!("docker.io/{}", reference),
+            _ => reference,
+        }
+    }
+
+    fn sandbox_args(&self, dc: &mut Command) {
+        let sandbox = &self.sandbox;
+        dc.arg("--network")
+            .arg("none")
+            .arg("--cap-drop")
+            .arg("ALL")
+            .arg("--security-opt")
+            .arg("no-new-privileges")
+            .arg("--read-only")
+            .arg("--tmpfs")
+            .arg("/tmp:rw,size=512m")
+            .arg("-e")
+            .arg("HOME=/tmp")
+            .arg("--pids-limit")
+            .arg(sandbox.pids_limit.to_string())
+            .arg("--cpus")
+            .arg(sandbox.cpus.to_string());
+        match (sandbox.user.as_ref(), HOST_USER.as_ref()) {
+            (Some(user), _) => {
+                dc.arg("--user").arg(user);
+            }
+            // the point is not to run tools as root, even when solsa is
+            (None, Some((uid, _))) if uid == "0" => {
+                dc.arg("--user").arg("65534:65534");
+            }
+            // rootless podman maps the host user to the same uid in the
+            // container, so mounted files keep their ownership
+            (None, Some(_)) if self.kind == RuntimeKind::Podman => {
+                dc.arg("--userns=keep-id");
+            }
+            (None, Some((uid, gid))) => {
+                dc.arg("--user").arg(format!("{}:{}", uid, gid));
+            }
+            (None, None) => {}
+        }
+    }
+
+    // The command and the name of the container it starts
+    pub fn run_cmd(&self, tool: &str) -> Result<(Command, String), SolsaError> {
+        let cwd = env::current_dir().map_err(|e| SolsaError::io(".", &e))?;
+        let name = format!(
+            "solsa-{}-{}-{}",
+            process::id(),
+            tool,
+            CONTAINERS.fetch_add(1, Ordering::SeqCst)
+        );
+        let mut dc = self.command();
         dc.arg("run")
             .arg("--rm")
+            .arg("--name")
+            .arg(&name)
             .arg("-m")
-            .arg("1.5g")
-            .arg("-v")
-            .arg(format!("{}:/src:ro", env::current_dir().unwrap().display()))
-            .arg(format!("enhancedsociety/{}", $e));
-        dc
-    }};
+            .arg(CONTAINER_MEMORY.to_string());
+        if self.kind == RuntimeKind::Podman {
+            // lets the container read the mounted directory on SELinux
+            // hosts without relabeling the user's files
+            dc.arg("--security-opt").arg("label=disable");
+        }
+        if self.sandbox.enabled {
+            self.sandbox_args(&mut dc);
+        }
+        dc.arg("-v")
+            .arg(format!("{}:/src:ro", cwd.display()))
+            .arg(self.image(tool));
+        Ok((dc, name))
+    }
+
+    // Stopping the `run` client does not stop the container
+    pub fn kill(&self, container: &str) {
+        debug!("stopping container {}", container);
+        let _ = self.command().arg("kill").arg(container).output();
+    }
+
+    // Memory available to containers, as the runtime reports it
+    pub fn memory(&self) -> Option<u64> {
+        let format = match self.kind {
+            RuntimeKind::Docker => "{{.MemTotal}}",
+            RuntimeKind::Podman => "{{.Host.MemTotal}}",
+        };
+        let output = self.command()
+            .arg("info")
+            .arg("--format")
+            .arg(format)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let memory = String::from_utf8_lossy(&output.stdout).trim().parse().ok();
+        if let Some(m) = memory {
+            debug!("{} has {} bytes of memory for containers", self.name(), m);
+        }
+        memory
+    }
+
+    // The progress goes to stderr, stdout may well be the report
+    pub fn pull(&self, tool: &str) -> io::Result<ExitStatus> {
+        debug!("pulling {}", self.image(tool));
+        self.command()
+            .arg("pull")
+            .arg(self.image(tool))
+            .stdout(Stdio::from(io::stderr()))
+            .status()
+    }
+
+    // Identifies the exact image a tool runs from, `None` when not available
+    // locally. Images built locally have no digest until they are pushed.
+    pub fn image_info(&self, tool: &str) -> Option<ImageInfo> {
+        let reference = self.image(tool);
+        self.command()
+            .arg("image")
+            .arg("inspect")
+            .arg("--format")
+            .arg("{{.Id}}{{range .RepoDigests}} {{.}}{{end}}")
+            .arg(&reference)
+            .output()
+            .ok()
+            .and_then(|output| {
+                if output.status.success() {
+                    String::from_utf8(output.stdout).ok()
+                } else {
+                    None
+                }
+            })
+            .and_then(|s| {
+                let mut fields = s.split_whitespace();
+                let id = fields.next()?.to_owned();
+                let digest = match reference.find('@') {
+                    Some(i) => Some(reference[i + 1..].to_owned()),
+                    None => fields
+                        .next()
+                        .and_then(|d| d.split('@').nth(1))
+                        .map(|d| d.to_owned()),
+                };
+                Some(ImageInfo { id, digest })
+            })
+    }
 }
@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use backend::{Backends, NativeTool};
+use cache::Cache;
+use config::Config;
+use docker::{self, Runtime, RuntimeKind};
+use images;
+
+const REQUIRED_MEMORY: u64 = docker::CONTAINER_MEMORY;
+const REQUIRED_DISK: u64 = 2 * 1024 * 1024 * 1024;
+
+// Permission bits of a file, `None` when it can not be read
+#[cfg(unix)]
+fn mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).ok().map(|m| m.permissions().mode())
+}
+
+// Elsewhere there are no such bits, anything that can be read counts as
+// readable by everyone
+#[cfg(not(unix))]
+fn mode(path: &Path) -> Option<u32> {
+    fs::metadata(path).ok().map(|_| 0o755)
+}
+
+#[derive(PartialEq)]
+enum Status {
+    Ok,
+    Warning,
+    Failed,
+}
+
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    remediation: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &str, detail: String) -> Check {
+        Check {
+            status: Status::Ok,
+            name: name.to_owned(),
+            detail,
+            remediation: None,
+        }
+    }
+
+    fn warning(name: &str, detail: String, remediation: String) -> Check {
+        Check {
+            status: Status::Warning,
+            name: name.to_owned(),
+            detail,
+            remediation: Some(remediation),
+        }
+    }
+
+    fn failed(name: &str, detail: String, remediation: String) -> Check {
+        Check {
+            status: Status::Failed,
+            name: name.to_owned(),
+            detail,
+            remediation: Some(remediation),
+        }
+    }
+
+    fn print(&self) {
+        let label = match self.status {
+            Status::Ok => "ok  ",
+            Status::Warning => "warn",
+            Status::Failed => "FAIL",
+        };
+        println!("[{}] {}: {}", label, self.name, self.detail);
+        if let Some(ref r) = self.remediation {
+            for line in r.lines() {
+                println!("       -> {}", line);
+            }
+        }
+    }
+}
+
+fn first_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())
+        .unwrap_or("")
+        .to_owned()
+}
+
+fn info(runtime: &Runtime, format: &str) -> Option<String> {
+    runtime
+        .command()
+        .arg("info")
+        .arg("--format")
+        .arg(format)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| first_line(&o.stdout))
+        .filter(|s| !s.is_empty())
+}
+
+// free bytes on the filesystem holding `path`, as reported by `df`
+fn free_space(path: &Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()
+        .map(|kb| kb * 1024)
+}
+
+fn gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// Without a runtime, solsa can still run the native tools
+fn check_runtime(config: &Config, has_native: bool) -> (Check, Option<Runtime>) {
+    match Runtime::detect(config.runtime.as_deref()) {
+        Ok(mut r) => {
+            r.sandbox = config.sandbox.clone();
+            r.images = config.images.clone();
+            let version = r.command()
+                .arg("--version")
+                .output()
+                .map(|o| first_line(&o.stdout))
+                .unwrap_or_default();
+            let check = Check::ok(
+                "container runtime",
+                format!("{} ({}) {}", r.name(), r.binary.display(), version),
+            );
+            (check, Some(r))
+        }
+        Err(e) => (
+            Check {
+                status: if has_native {
+                    Status::Warning
+                } else {
+                    Status::Failed
+                },
+                name: "container runtime".to_owned(),
+                detail: if has_native {
+                    format!("{}, tools that run in containers will be skipped", e)
+                } else {
+                    e
+                },
+                remediation: Some(format!(
+                    "install docker (https://docs.docker.com/install/) or podman (https://podman.io/)\n\
+                     or point solsa at the binary with {}=/path/to/runtime or `runtime` in solsa.toml\n\
+                     or run the tools from the host with --native",
+                    docker::RUNTIME_ENV
+                )),
+            },
+            None,
+        ),
+    }
+}
+
+fn check_daemon(runtime: &Runtime) -> Check {
+    let output = runtime.command().arg("info").output();
+    match output {
+        Ok(ref o) if o.status.success() => Check::ok(
+            "daemon",
+            format!("{} is reachable", runtime.name()),
+        ),
+        Ok(o) => Check::failed(
+            "daemon",
+            first_line(&o.stderr),
+            match runtime.kind {
+                RuntimeKind::Docker => "start the docker daemon, e.g. `sudo systemctl start docker`\n\
+                     make sure your user can use it, e.g. `sudo usermod -aG docker $USER` and log in again\n\
+                     check DOCKER_HOST if the daemon is remote"
+                    .to_owned(),
+                RuntimeKind::Podman => "check `podman info` for the actual error\n\
+                     rootless podman needs entries for your user in /etc/subuid and /etc/subgid"
+                    .to_owned(),
+            },
+        ),
+        Err(e) => Check::failed(
+            "daemon",
+            format!("failed to run {}: {}", runtime.binary.display(), e),
+            "check that the runtime binary is executable".to_owned(),
+        ),
+    }
+}
+
+fn check_memory(runtime: &Runtime) -> Check {
+    match runtime.memory() {
+        Some(m) if m >= REQUIRED_MEMORY => Check::ok(
+            "memory",
+            format!("{} available to containers", gib(m)),
+        ),
+        Some(m) => Check::failed(
+            "memory",
+            format!(
+                "{} available to containers, tools run with a {} limit",
+                gib(m),
+                gib(REQUIRED_MEMORY)
+            ),
+            "give the runtime more memory, e.g. in Docker Desktop's resources settings".to_owned(),
+        ),
+        None => Check::warning(
+            "memory",
+            "could not tell how much memory is available to containers".to_owned(),
+            format!(
+                "make sure at least {} are available to containers",
+                gib(REQUIRED_MEMORY)
+            ),
+        ),
+    }
+}
+
+fn check_disk(name: &str, path: &Path, remediation: &str) -> Option<Check> {
+    free_space(path).map(|free| {
+        if free >= REQUIRED_DISK {
+            Check::ok(name, format!("{} free on {}", gib(free), path.display()))
+        } else {
+            Check::warning(
+                name,
+                format!("only {} free on {}", gib(free), path.display()),
+                remediation.to_owned(),
+            )
+        }
+    })
+}
+
+fn check_image(backends: &Backends, runtime: &Runtime, tool: &str) -> Check {
+    let name = format!("image {}", tool);
+    let reference = runtime.image(tool);
+    match runtime.image_info(tool) {
+        None => Check::failed(
+            &name,
+            format!("{} is not available locally", reference),
+            format!(
+                "pull it with `{} pull {}` or run solsa with --preload",
+                runtime.name(),
+                reference
+            ),
+        ),
+        Some(info) => {
+            let digest = info.digest.unwrap_or(info.id);
+            match backends.version(tool) {
+                Some(v) => Check::ok(&name, format!("{} ({}), {}", reference, digest, v)),
+                // the flattener has no --version
+                None if tool == "solidity-flattener" => {
+                    Check::ok(&name, format!("{} ({})", reference, digest))
+                }
+                None if runtime.sandbox.enabled => Check::warning(
+                    &name,
+                    format!("{} ({}) is present but failed to run", reference, digest),
+                    "try again with --no-sandbox, if that works the image needs a writable root or network access"
+                        .to_owned(),
+                ),
+                None => Check::warning(
+                    &name,
+                    format!("{} ({}) is present but failed to run", reference, digest),
+                    format!("try `{} run --rm {} --version`", runtime.name(), reference),
+                ),
+            }
+        }
+    }
+}
+
+fn check_native(tool: &str) -> (Check, Option<NativeTool>) {
+    let name = format!("native {}", tool);
+    match NativeTool::find(tool) {
+        Ok(n) => (
+            Check::ok(&name, format!("{} {}", n.binary.display(), n.version)),
+            Some(n),
+        ),
+        Err(e) => (
+            Check::failed(
+                &name,
+                e,
+                format!("install a supported version of {} or drop it from --native", tool),
+            ),
+            None,
+        ),
+    }
+}
+
+// The current directory is mounted into every container, which runs as the
+// host user (or nobody when that is root)
+fn check_mount(sandboxed: bool) -> Check {
+    let cwd = match env::current_dir() {
+        Ok(d) => d,
+        Err(e) => {
+            return Check::failed(
+                "mount path",
+                format!("can not read the current directory: {}", e),
+                "run solsa from the project directory".to_owned(),
+            )
+        }
+    };
+    if let Err(e) = fs::read_dir(&cwd) {
+        return Check::failed(
+            "mount path",
+            format!("{} can not be listed: {}", cwd.display(), e),
+            format!("check the permissions of {}", cwd.display()),
+        );
+    }
+    if sandboxed && docker::host_uid().map(|u| u == "0").unwrap_or(false) {
+        let not_world_readable = fs::read_dir(&cwd)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().map(|e| e == "sol").unwrap_or(false))
+                    .filter(|p| mode(p).map(|m| m & 0o004 == 0).unwrap_or(true))
+                    .count()
+            })
+            .unwrap_or(0);
+        let cwd_mode = mode(&cwd).unwrap_or(0);
+        if not_world_readable > 0 || cwd_mode & 0o005 != 0o005 {
+            return Check::warning(
+                "mount path",
+                format!(
+                    "solsa runs as root, so tools run as nobody and can not read {} or some of its contracts",
+                    cwd.display()
+                ),
+                "make the sources world readable with `chmod -R o+rX .`\n\
+                 or set `user` in the [sandbox] table of solsa.toml"
+                    .to_owned(),
+            );
+        }
+    }
+    Check::ok("mount path", format!("{} is readable", cwd.display()))
+}
+
+// Prints the outcome of every check, returns whether solsa can run
+pub fn doctor(config: &Config, native: &[String]) -> bool {
+    let mut checks = Vec::new();
+
+    let mut native_tools = BTreeMap::new();
+    for tool in native {
+        let (check, n) = check_native(tool);
+        checks.push(check);
+        if let Some(n) = n {
+            native_tools.insert(tool.clone(), n);
+        }
+    }
+
+    let (check, runtime) = check_runtime(config, !native.is_empty());
+    checks.push(check);
+    let backends = Backends {
+        runtime,
+        native: native_tools,
+        timeout: config.timeout,
+        keep_raw: None,
+    };
+
+    if let Some(ref runtime) = backends.runtime {
+        let daemon = check_daemon(runtime);
+        let reachable = daemon.status == Status::Ok;
+        checks.push(daemon);
+        if reachable {
+            checks.push(check_memory(runtime));
+            let root_dir = match runtime.kind {
+                RuntimeKind::Docker => info(runtime, "{{.DockerRootDir}}"),
+                RuntimeKind::Podman => info(runtime, "{{.Store.GraphRoot}}"),
+            };
+            if let Some(check) = root_dir.and_then(|d| {
+                check_disk(
+                    "image storage",
+                    Path::new(&d),
+                    &format!("free up space, e.g. with `{} system prune`", runtime.name()),
+                )
+            }) {
+                checks.push(check);
+            }
+            for tool in images::TOOLS.iter().filter(|t| !backends.is_native(t)) {
+                checks.push(check_image(&backends, runtime, tool));
+            }
+        }
+    }
+
+    if let Some(check) = Cache::open().and_then(|c| {
+        let dir = c.dir().clone();
+        // the cache directory may not exist yet
+        let existing = dir.ancestors()
+            .find(|d| d.exists())
+            .map(|d| d.to_path_buf())
+            .unwrap_or(dir);
+        check_disk(
+            "cache storage",
+            &existing,
+            "free up space, or clean the results cache with `solsa cache clean`",
+        )
+    }) {
+        checks.push(check);
+    }
+
+    checks.push(check_mount(
+        backends.runtime
+            .as_ref()
+            .map(|r| r.sandbox.enabled)
+            .unwrap_or(false),
+    ));
+
+    for check in &checks {
+        check.print();
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Failed).count();
+    let warnings = checks.iter().filter(|c| c.status == Status::Warning).count();
+    println!();
+    println!("{} problem(s), {} warning(s)", failed, warnings);
+    failed == 0
+}
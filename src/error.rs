@@ -0,0 +1,109 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// Exit codes, documented in the README, so CI can tell a vulnerable contract
+// apart from an analysis that could not be completed
+pub const EXIT_CLEAN: i32 = 0;
+pub const EXIT_FINDINGS: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_TOOL_ERROR: i32 = 3;
+pub const EXIT_TIMEOUT: i32 = 4;
+
+/// Why a tool, or solsa itself, could not do its job. Kept in reports, so
+/// it can be serialized and read back.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SolsaError {
+    /// The tool could not be started at all, e.g. no container runtime
+    Spawn { tool: String, message: String },
+    /// The tool ran but exited with an error and no result, e.g. a contract
+    /// that does not compile, or the tool crashing
+    Crash {
+        tool: String,
+        status: Option<i32>,
+        output: String,
+    },
+    /// The tool's output could not be understood
+    Parse { tool: String, message: String },
+    Timeout { tool: String, seconds: u64 },
+    Io { path: String, message: String },
+    /// Invalid settings, or an environment solsa can not work in
+    Config { message: String },
+}
+
+impl SolsaError {
+    pub fn io(path: &str, e: &io::Error) -> SolsaError {
+        SolsaError::Io {
+            path: path.to_owned(),
+            message: e.to_string(),
+        }
+    }
+
+    pub fn config<S: Into<String>>(message: S) -> SolsaError {
+        SolsaError::Config {
+            message: message.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            SolsaError::Spawn { .. } => "spawn",
+            SolsaError::Crash { .. } => "crash",
+            SolsaError::Parse { .. } => "parse",
+            SolsaError::Timeout { .. } => "timeout",
+            SolsaError::Io { .. } => "io",
+            SolsaError::Config { .. } => "config",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            SolsaError::Config { .. } => EXIT_USAGE,
+            SolsaError::Timeout { .. } => EXIT_TIMEOUT,
+            _ => EXIT_TOOL_ERROR,
+        }
+    }
+
+    // For reports with room for a single line, e.g. a status table
+    pub fn summary(&self) -> String {
+        match *self {
+            SolsaError::Crash {
+                ref tool,
+                status: Some(s),
+                ..
+            } => format!("{} exited with status {}", tool, s),
+            SolsaError::Crash { ref tool, .. } => format!("{} failed", tool),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SolsaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SolsaError::Spawn {
+                ref tool,
+                ref message,
+            } => write!(f, "{} could not be started: {}", tool, message),
+            SolsaError::Crash { ref output, .. } if !output.trim().is_empty() => {
+                write!(f, "{}:\n{}", self.summary(), output.trim_end())
+            }
+            SolsaError::Crash { .. } => write!(f, "{}", self.summary()),
+            SolsaError::Parse {
+                ref tool,
+                ref message,
+            } => write!(f, "could not make sense of {} output: {}", tool, message),
+            SolsaError::Timeout { ref tool, seconds } => {
+                write!(f, "{} timed out after {}s", tool, seconds)
+            }
+            SolsaError::Io {
+                ref path,
+                ref message,
+            } => write!(f, "{}: {}", path, message),
+            SolsaError::Config { ref message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for SolsaError {}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use error::SolsaError;
+use tools;
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn from_label(label: &str) -> Severity {
+        match label.trim().to_lowercase().as_str() {
+            "error" => Severity::Error,
+            "warning" => Severity::Warning,
+            _ => Severity::Info,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolStatus {
+    Ok,
+    Issues,
+    Failed,
+    // left out with `--tools`/`--skip-tools`, which says nothing about the contract
+    #[serde(rename = "not_run")]
+    NotRun,
+}
+
+/// A single issue reported by any of the tools, normalized so reports can
+/// treat every tool the same way.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Finding {
+    pub tool: String,
+    pub severity: Severity,
+    pub filename: String,
+    pub line: u32,
+    pub column: u32,
+    /// Contract the finding belongs to, or the source file when the tool
+    /// does not report one
+    pub contract: String,
+    pub rule: String,
+    pub message: String,
+    pub details: Option<String>,
+}
+
+impl Finding {
+    // Deliberately leaves out line and column, so the fingerprint survives
+    // unrelated changes that move the code around
+    pub fn fingerprint(&self) -> String {
+        let key = [
+            self.tool.as_str(),
+            self.rule.as_str(),
+            self.filename.as_str(),
+            self.contract.as_str(),
+            self.message.as_str(),
+        ].join("\u{0}");
+        format!("{:016x}", fnv1a(key.as_bytes()))
+    }
+}
+
+// FNV-1a is stable across platforms and releases, unlike std's hashers
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+// Identical findings (e.g. the same issue on several lines) would share a
+// fingerprint, so their order of appearance is mixed in to tell them apart
+pub fn fingerprints<'a, I>(findings: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a Finding>,
+{
+    let mut seen = HashMap::new();
+    findings
+        .into_iter()
+        .map(|f| {
+            let fp = f.fingerprint();
+            let occurrence = seen.entry(fp.clone()).or_insert(0);
+            *occurrence += 1;
+            if *occurrence == 1 {
+                fp
+            } else {
+                format!("{}-{}", fp, occurrence)
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ToolFindings {
+    pub tool: String,
+    pub status: ToolStatus,
+    pub findings: Vec<Finding>,
+    // why the tool failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<SolsaError>,
+}
+
+impl ToolFindings {
+    fn new(tool: &str, status: ToolStatus, findings: Vec<Finding>) -> ToolFindings {
+        ToolFindings {
+            tool: tool.to_owned(),
+            status,
+            findings,
+            error: None,
+        }
+    }
+
+    fn failed(tool: &str, error: &SolsaError) -> ToolFindings {
+        ToolFindings {
+            error: Some(error.clone()),
+            ..ToolFindings::new(tool, ToolStatus::Failed, vec![])
+        }
+    }
+
+    fn not_run(tool: &str) -> ToolFindings {
+        ToolFindings::new(tool, ToolStatus::NotRun, vec![])
+    }
+
+    fn from_findings(tool: &str, findings: Vec<Finding>) -> ToolFindings {
+        let status = if findings.is_empty() {
+            ToolStatus::Ok
+        } else {
+            ToolStatus::Issues
+        };
+        ToolFindings::new(tool, status, findings)
+    }
+}
+
+// solc and solium both report in the gcc format
+// filename:line:column: type: message
+pub fn parse_gcc_line(tool: &str, line: &str) -> Option<Finding> {
+    let components = line.splitn(5, ':').collect::<Vec<&str>>();
+    if components.len() != 5 {
+        return None;
+    }
+    match (components[1].parse::<u32>(), components[2].parse::<u32>()) {
+        (Ok(l), Ok(c)) => Some(Finding {
+            tool: tool.to_owned(),
+            severity: Severity::from_label(components[3]),
+            filename: components[0].to_owned(),
+            line: l,
+            column: c,
+            contract: components[0].to_owned(),
+            rule: components[3].trim().to_lowercase(),
+            message: components[4].trim().to_owned(),
+            details: None,
+        }),
+        _ => None,
+    }
+}
+
+pub fn collect(responses: &tools::ToolResponses) -> Vec<ToolFindings> {
+    vec![
+        from_solc(&responses.solc),
+        from_solium(&responses.solium),
+        from_mythril(&responses.mythril),
+        from_oyente(&responses.oyente),
+    ]
+}
+
+pub fn from_solc(out: &Option<tools::SolcResponse>) -> ToolFindings {
+    match *out {
+        Some(tools::SolcResponse::Success(_)) => ToolFindings::from_findings("solc", vec![]),
+        // compilation errors are reported as findings, so they show up next
+        // to the rest
+        Some(tools::SolcResponse::Failure(ref e)) => {
+            let findings = match *e {
+                SolsaError::Crash { ref output, .. } => output
+                    .lines()
+                    .filter_map(|l| parse_gcc_line("solc", l))
+                    .collect(),
+                _ => vec![],
+            };
+            ToolFindings {
+                findings,
+                ..ToolFindings::failed("solc", e)
+            }
+        }
+        None => ToolFindings::not_run("solc"),
+    }
+}
+
+pub fn from_solium(out: &Option<tools::SoliumResponse>) -> ToolFindings {
+    match *out {
+        Some(tools::SoliumResponse::Success(ref issues)) => ToolFindings::from_findings(
+            "solium",
+            issues.iter().map(|i| i.to_finding()).collect(),
+        ),
+        Some(tools::SoliumResponse::Failure(ref e)) => ToolFindings::failed("solium", e),
+        None => ToolFindings::not_run("solium"),
+    }
+}
+
+pub fn from_mythril(out: &Option<tools::MythrilResponse>) -> ToolFindings {
+    match *out {
+        Some(tools::MythrilResponse::Success(ref o)) if o.success => {
+            ToolFindings::from_findings("mythril", o.findings())
+        }
+        // mythril reports its own errors in the json output
+        Some(tools::MythrilResponse::Success(ref o)) => ToolFindings::failed(
+            "mythril",
+            &SolsaError::Crash {
+                tool: "mythril".to_owned(),
+                status: None,
+                output: o.error.clone().unwrap_or_default(),
+            },
+        ),
+        Some(tools::MythrilResponse::Failure(ref e)) => ToolFindings::failed("mythril", e),
+        None => ToolFindings::not_run("mythril"),
+    }
+}
+
+pub fn from_oyente(out: &Option<tools::OyenteResponse>) -> ToolFindings {
+    match *out {
+        Some(tools::OyenteResponse::Success(ref o, _)) => {
+            ToolFindings::from_findings("oyente", o.findings())
+        }
+        Some(tools::OyenteResponse::Failure(ref e)) => ToolFindings::failed("oyente", e),
+        None => ToolFindings::not_run("oyente"),
+    }
+}
+
+// A solium warning, the fixture of tests comparing and rendering findings
+#[cfg(test)]
+pub fn finding(filename: &str, line: u32, message: &str) -> Finding {
+    Finding {
+        tool: "solium".to_owned(),
+        severity: Severity::Warning,
+        filename: filename.to_owned(),
+        line,
+        column: 1,
+        contract: filename.to_owned(),
+        rule: "warning".to_owned(),
+        message: message.to_owned(),
+        details: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_ignores_position() {
+        assert_eq!(
+            finding("A.sol", 3, "Avoid using 'now'.").fingerprint(),
+            Finding {
+                column: 7,
+                ..finding("A.sol", 40, "Avoid using 'now'.")
+            }.fingerprint()
+        );
+        assert_ne!(
+            finding("A.sol", 3, "Avoid using 'now'.").fingerprint(),
+            finding("A.sol", 3, "Use emit statements.").fingerprint()
+        );
+    }
+
+    #[test]
+    fn duplicates_get_an_occurrence_suffix() {
+        let findings = vec![
+            finding("A.sol", 3, "Avoid using 'now'."),
+            finding("A.sol", 5, "Use emit statements."),
+            finding("A.sol", 9, "Avoid using 'now'."),
+            finding("A.sol", 12, "Avoid using 'now'."),
+        ];
+        let fps = fingerprints(&findings);
+        let base = findings[0].fingerprint();
+        assert_eq!(fps[0], base);
+        assert_eq!(fps[1], findings[1].fingerprint());
+        assert_eq!(fps[2], format!("{}-2", base));
+        assert_eq!(fps[3], format!("{}-3", base));
+    }
+}
@@ -0,0 +1,313 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command, ExitStatus};
+
+use serde_json;
+
+use docker::{Runtime, RuntimeKind};
+use error::SolsaError;
+
+// Every image solsa runs tools from
+pub const TOOLS: &[&str] = &["solc", "solidity-flattener", "solium", "oyente", "mythril"];
+
+// Name of the manifest added to exported image bundles
+const BUNDLE_MANIFEST: &str = "solsa-images.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BundleImage {
+    tool: String,
+    reference: String,
+    id: String,
+    digest: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BundleManifest {
+    solsa_version: String,
+    images: Vec<BundleImage>,
+}
+
+fn temp_dir(purpose: &str) -> Result<PathBuf, SolsaError> {
+    let dir = env::temp_dir().join(format!("solsa-{}-{}", purpose, process::id()));
+    fs::create_dir_all(&dir).map_err(|e| SolsaError::io(&dir.display().to_string(), &e))?;
+    Ok(dir)
+}
+
+fn status(program: &str, cmd: &mut Command) -> Result<ExitStatus, SolsaError> {
+    cmd.status().map_err(|e| SolsaError::Spawn {
+        tool: program.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+// A runtime (or tar) command that ran but did not succeed, a tool error
+// rather than a usage one
+fn failed<S: Into<String>>(program: &str, status: Option<ExitStatus>, message: S) -> SolsaError {
+    SolsaError::Crash {
+        tool: program.to_owned(),
+        status: status.and_then(|s| s.code()),
+        output: message.into(),
+    }
+}
+
+// Bundled at compile time, so images can be built from an installed solsa
+const DOCKERFILES: &[(&str, &str)] = &[
+    ("Dockerfile.solc", include_str!("../dockerfiles/Dockerfile.solc")),
+    (
+        "Dockerfile.solidity-flattener",
+        include_str!("../dockerfiles/Dockerfile.solidity-flattener"),
+    ),
+    ("Dockerfile.solium", include_str!("../dockerfiles/Dockerfile.solium")),
+    ("Dockerfile.oyente", include_str!("../dockerfiles/Dockerfile.oyente")),
+    ("Dockerfile.mythril", include_str!("../dockerfiles/Dockerfile.mythril")),
+    ("oyente.sh", include_str!("../dockerfiles/oyente.sh")),
+];
+
+// The build context, removed once done when it was written by solsa
+struct Context {
+    dir: PathBuf,
+    bundled: bool,
+}
+
+impl Context {
+    fn new(dockerfiles: Option<&str>) -> Result<Context, SolsaError> {
+        match dockerfiles {
+            Some(d) => Ok(Context {
+                dir: PathBuf::from(d),
+                bundled: false,
+            }),
+            None => {
+                let context = Context {
+                    dir: temp_dir("images")?,
+                    bundled: true,
+                };
+                for &(name, contents) in DOCKERFILES {
+                    let path = context.dir.join(name);
+                    fs::write(&path, contents)
+                        .map_err(|e| SolsaError::io(&path.display().to_string(), &e))?;
+                }
+                Ok(context)
+            }
+        }
+    }
+
+    fn dockerfile(&self, tool: &str) -> PathBuf {
+        self.dir.join(format!("Dockerfile.{}", tool))
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        if self.bundled {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+// Images are tagged with the reference solsa runs the tool from, i.e. the one
+// in the image manifest or `enhancedsociety/<tool>`, so they are used right away
+pub fn build(
+    runtime: &Runtime,
+    tools: &[&str],
+    build_args: &[&str],
+    dockerfiles: Option<&str>,
+) -> Result<(), SolsaError> {
+    let context = Context::new(dockerfiles)?;
+    for tool in tools {
+        let tag = runtime.image(tool);
+        if tag.contains('@') {
+            return Err(SolsaError::config(format!(
+                "{} is pinned to {} in the image manifest, a local build can not match that digest, \
+                 pin it to a tag instead",
+                tool, tag
+            )));
+        }
+        let dockerfile = context.dockerfile(tool);
+        if !dockerfile.is_file() {
+            return Err(SolsaError::config(format!(
+                "{} does not exist",
+                dockerfile.display()
+            )));
+        }
+
+        println!("Building {} as {}", tool, tag);
+        let mut cmd = runtime.command();
+        cmd.arg("build").arg("-t").arg(&tag).arg("-f").arg(&dockerfile);
+        for arg in build_args {
+            cmd.arg("--build-arg").arg(arg);
+        }
+        cmd.arg(&context.dir);
+        let built = status(runtime.name(), &mut cmd)?;
+        if !built.success() {
+            return Err(failed(
+                runtime.name(),
+                Some(built),
+                format!("Failed to build image for {}", tool),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// A single archive loadable with `docker load`, with a manifest of the images
+// it holds added next to the runtime's own
+pub fn export(runtime: &Runtime, tarball: &str) -> Result<(), SolsaError> {
+    let images = TOOLS
+        .iter()
+        .map(|tool| {
+            let info = runtime.image_info(tool).ok_or_else(|| {
+                SolsaError::config(format!(
+                    "{} is not available locally, pull it first (e.g. with --preload)",
+                    runtime.image(tool)
+                ))
+            })?;
+            Ok(BundleImage {
+                tool: tool.to_string(),
+                reference: runtime.image(tool),
+                id: info.id,
+                digest: info.digest,
+            })
+        })
+        .collect::<Result<Vec<_>, SolsaError>>()?;
+
+    println!("Saving {} images to {}", images.len(), tarball);
+    let mut save = runtime.command();
+    save.arg("save").arg("-o").arg(tarball);
+    if runtime.kind == RuntimeKind::Podman {
+        save.arg("--multi-image-archive");
+    }
+    for image in &images {
+        save.arg(&image.reference);
+    }
+    let saved = status(runtime.name(), &mut save)?;
+    if !saved.success() {
+        return Err(failed(
+            runtime.name(),
+            Some(saved),
+            format!("Failed to save images to {}", tarball),
+        ));
+    }
+
+    let manifest = BundleManifest {
+        solsa_version: crate_version!().to_owned(),
+        images,
+    };
+    let dir = temp_dir("export")?;
+    let manifest_path = dir.join(BUNDLE_MANIFEST);
+    let appended = fs::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest).expect("Failed to serialize manifest"),
+    ).map_err(|e| SolsaError::io(&manifest_path.display().to_string(), &e))
+        .and_then(|_| {
+            status(
+                "tar",
+                Command::new("tar")
+                    .arg("-rf")
+                    .arg(tarball)
+                    .arg("-C")
+                    .arg(&dir)
+                    .arg(BUNDLE_MANIFEST),
+            )
+        });
+    let _ = fs::remove_dir_all(&dir);
+    let appended = appended?;
+    if !appended.success() {
+        return Err(failed(
+            "tar",
+            Some(appended),
+            format!("Failed to add the manifest to {}", tarball),
+        ));
+    }
+    Ok(())
+}
+
+fn read_manifest(tarball: &str) -> Result<BundleManifest, SolsaError> {
+    let output = Command::new("tar")
+        .arg("-xOf")
+        .arg(tarball)
+        .arg(BUNDLE_MANIFEST)
+        .output()
+        .map_err(|e| SolsaError::Spawn {
+            tool: "tar".to_owned(),
+            message: e.to_string(),
+        })?;
+    if !output.status.success() {
+        return Err(SolsaError::config(format!(
+            "{} is not a solsa image bundle, it has no {}",
+            tarball, BUNDLE_MANIFEST
+        )));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| {
+        SolsaError::config(format!("Invalid manifest in {}: {}", tarball, e))
+    })
+}
+
+// Loads a bundle made by `export` and checks every image solsa runs tools
+// from is now the one in the bundle
+pub fn import(runtime: &Runtime, tarball: &str) -> Result<(), SolsaError> {
+    let manifest = read_manifest(tarball)?;
+
+    println!("Loading images from {}", tarball);
+    let loaded = status(
+        runtime.name(),
+        runtime.command().arg("load").arg("-i").arg(tarball),
+    )?;
+    if !loaded.success() {
+        return Err(failed(
+            runtime.name(),
+            Some(loaded),
+            format!("Failed to load images from {}", tarball),
+        ));
+    }
+
+    let mut problems = Vec::new();
+    for image in &manifest.images {
+        let reference = runtime.image(&image.tool);
+        match runtime.image_info(&image.tool) {
+            Some(ref info) if info.id == image.id => {
+                println!("{}: {} ({})", image.tool, reference, image.id);
+            }
+            Some(_) => problems.push(format!(
+                "{}: solsa runs {}, which is not the image in the bundle ({})",
+                image.tool, reference, image.reference
+            )),
+            None => problems.push(format!(
+                "{}: {} is not available after loading the bundle, which holds {}",
+                image.tool, reference, image.reference
+            )),
+        }
+    }
+    for tool in TOOLS {
+        if !manifest.images.iter().any(|i| i.tool == *tool) {
+            problems.push(format!("{}: not in the bundle", tool));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(failed(runtime.name(), None, problems.join("\n")));
+    }
+    Ok(())
+}
+
+// Run before an analysis, so a missing image is pulled, or found missing on an
+// air-gapped machine, before any tool runs rather than halfway through
+pub fn verify(runtime: &Runtime, tools: &[&str]) -> Result<(), SolsaError> {
+    for tool in tools {
+        if runtime.image_info(tool).is_some() {
+            continue;
+        }
+        let pulled = runtime.pull(tool).map(|s| s.success()).unwrap_or(false);
+        if !pulled {
+            return Err(SolsaError::Spawn {
+                tool: tool.to_string(),
+                message: format!(
+                    "{} is not available locally and could not be pulled, \
+                     import an image bundle with `solsa images import <tarball>` or build it with `solsa images build {}`",
+                    runtime.image(tool),
+                    tool
+                ),
+            });
+        }
+    }
+    Ok(())
+}
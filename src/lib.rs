@@ -0,0 +1,58 @@
+//! Aggregates static analysis tooling for ethereum smart contracts: solc,
+//! solium, mythril and oyente, run from their container images (or from the
+//! host), with their output normalized into findings.
+//!
+//! Start from `Analysis::builder`, the `solsa` command line tool is a thin
+//! wrapper over it.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+
+#[macro_use]
+extern crate serde_json;
+
+#[macro_use]
+extern crate clap;
+
+extern crate tera;
+extern crate toml;
+
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use]
+extern crate log;
+
+pub mod analysis;
+pub mod backend;
+pub mod cache;
+pub mod config;
+pub mod diff;
+pub mod docker;
+pub mod error;
+pub mod findings;
+pub mod report;
+pub mod scheduler;
+pub mod tool_output;
+pub mod tools;
+
+// support for the command line tool's subcommands, not part of the API
+#[doc(hidden)]
+pub mod doctor;
+#[doc(hidden)]
+pub mod images;
+#[doc(hidden)]
+pub mod logger;
+#[doc(hidden)]
+pub mod progress;
+#[doc(hidden)]
+pub mod watch;
+
+pub use analysis::{Analysis, AnalysisBuilder, ContractResult, Invocation, Job};
+pub use config::Config;
+pub use error::SolsaError;
+pub use findings::{Finding, Severity, ToolFindings, ToolStatus};
+pub use scheduler::Progress;
+pub use tools::{AnalysisDepth, MythrilOptions, OyenteOptions, ToolResponses};
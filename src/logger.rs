@@ -0,0 +1,70 @@
+use std::io::{self, Write};
+
+use log::kv::{self, Key, Value, VisitSource};
+use log::{self, LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value as Json};
+
+// Prints solsa's own log records on stderr, either as plain lines or as one
+// JSON object per line with the record's key-values as fields
+struct Logger {
+    json: bool,
+}
+
+struct Fields(Map<String, Json>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let v = if let Some(n) = value.to_u64() {
+            json!(n)
+        } else if let Some(n) = value.to_i64() {
+            json!(n)
+        } else if let Some(n) = value.to_f64() {
+            json!(n)
+        } else if let Some(b) = value.to_bool() {
+            json!(b)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.to_string(), v);
+        Ok(())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("solsa")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = if self.json {
+            let mut fields = Fields(Map::new());
+            let _ = record.key_values().visit(&mut fields);
+            let mut fields = fields.0;
+            fields.insert("level".to_owned(), json!(record.level().as_str().to_lowercase()));
+            fields.insert("target".to_owned(), json!(record.target()));
+            fields.insert("message".to_owned(), json!(record.args().to_string()));
+            Json::Object(fields).to_string()
+        } else {
+            format!("[{}] {}", record.level().as_str().to_lowercase(), record.args())
+        };
+        let _ = writeln!(io::stderr(), "{}", line);
+    }
+
+    fn flush(&self) {}
+}
+
+// 0 shows warnings only, 1 (-v) each tool's command line and how it exited,
+// 2 (-vv) and above everything down to the environment of each command
+pub fn init(verbosity: u64, json: bool) {
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    };
+    if log::set_boxed_logger(Box::new(Logger { json })).is_ok() {
+        log::set_max_level(level);
+    }
+}
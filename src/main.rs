@@ -1,7 +1,4 @@
-#[macro_use]
-extern crate serde_derive;
-
-extern crate serde;
+extern crate atty;
 
 #[macro_use]
 extern crate serde_json;
@@ -9,85 +6,511 @@ extern crate serde_json;
 #[macro_use]
 extern crate clap;
 
+extern crate solsa;
 extern crate tera;
 
-#[macro_use]
-extern crate lazy_static;
-
-use clap::{App, Arg, ArgGroup};
-
-use tera::{Context, Tera};
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
 
 use std::fs;
 
-use std::sync::Arc;
-use std::thread;
-
 use std::env;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[macro_use]
-mod docker;
-mod tool_output;
-mod tools;
-
-lazy_static! {
-    pub static ref TERA: Tera = {
-        // Get templates at compile time, remove a runtime dependency
-        let mut tera = Tera::default();
-        tera.add_raw_template("index.html", include_str!("../templates/index.html")).unwrap();
-        tera.register_filter("float", |s, _|
-        Ok(serde_json::value::to_value(
-            s.as_str().unwrap().parse::<f32>().unwrap()
-        ).unwrap()));
-        tera
-    };
-}
+use solsa::{
+    analysis, backend, cache, config, diff, doctor, error, findings, images, logger, progress, report,
+    tools, watch,
+};
+use solsa::{Analysis, SolsaError};
 
+#[derive(PartialEq)]
 enum OutputType {
     HTML,
     JSON,
+    Markdown,
+    Text,
+    GitLab,
+    Checkstyle,
     None,
 }
 
-fn docker_check(preload: bool) {
-    let docker_executables = env::var("PATH")
-        .unwrap_or_default()
-        .split(':')
-        .map(|p| format!("{}/docker", &p))
-        .filter(|p_str| fs::metadata(p_str).is_ok())
-        .collect::<Vec<String>>()
-        .len();
-
-    if docker_executables == 0 {
-        panic!("Docker does not seem to be installed and is required.");
-    }
-
-    if preload {
-        for tool in &["solc", "solidity-flattener", "solium", "oyente", "mythril"] {
-            let mut dc = Command::new("docker");
-            dc.arg("pull").arg(format!("enhancedsociety/{}", &tool));
-            dc.status()
-                .unwrap_or_else(|_| panic!("Failed to get docker image for {}", &tool));
+fn parse_depth(depth: Option<&str>) -> tools::AnalysisDepth {
+    match depth.unwrap_or("shallow") {
+        "deepest" => tools::AnalysisDepth::Deepest,
+        "deeper" => tools::AnalysisDepth::Deeper,
+        "deep" => tools::AnalysisDepth::Deep,
+        "shallow" | _ => tools::AnalysisDepth::Shallow,
+    }
+}
+
+fn is_number<T: FromStr>(v: String) -> Result<(), String> {
+    v.parse::<T>()
+        .map(|_| ())
+        .map_err(|_| "must be a whole number".to_owned())
+}
+
+fn number<T: FromStr>(matches: &ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).and_then(|v| v.parse().ok())
+}
+
+// `--json` writes a single report for one contract, an array of them for several
+fn read_report(path: &str) -> Result<Vec<(String, Vec<findings::ToolFindings>)>, SolsaError> {
+    let s = fs::read_to_string(path).map_err(|e| SolsaError::io(path, &e))?;
+    let invalid = |e: serde_json::Error| {
+        SolsaError::config(format!("{} is not a solsa JSON report: {}", path, e))
+    };
+    let value: serde_json::Value = serde_json::from_str(&s).map_err(invalid)?;
+    let reports: Vec<report::JsonReport> = if value.is_array() {
+        serde_json::from_value(value).map_err(invalid)?
+    } else {
+        vec![serde_json::from_value(value).map_err(invalid)?]
+    };
+    Ok(reports
+        .into_iter()
+        .map(|r| {
+            let file = r.contract_file.clone().unwrap_or_default();
+            (file, findings::collect(&r.into_responses()))
+        })
+        .collect())
+}
+
+fn write_file(path: &str, contents: &str) -> Result<(), SolsaError> {
+    fs::write(path, contents).map_err(|e| SolsaError::io(path, &e))
+}
+
+fn diff_command(matches: &ArgMatches) -> Result<(), SolsaError> {
+    let old_path = matches.value_of("old").expect("Old report is required");
+    let new_path = matches.value_of("new").expect("New report is required");
+    let tools_diff = diff::diff_contracts(&read_report(old_path)?, &read_report(new_path)?);
+
+    let output_path = matches.value_of("output");
+    let s = if matches.is_present("json") {
+        serde_json::to_string_pretty(&tools_diff).expect("Failed to serialize diff")
+    } else if matches.is_present("html") {
+        let (new, fixed, unchanged) = diff::totals(&tools_diff);
+        let mut ctx = tera::Context::new();
+        ctx.add("solsa_version", crate_version!());
+        ctx.add("old_report", &old_path);
+        ctx.add("new_report", &new_path);
+        ctx.add("tools", &tools_diff);
+        ctx.add(
+            "counts",
+            &json!({
+                "new": new,
+                "fixed": fixed,
+                "unchanged": unchanged,
+            }),
+        );
+        report::TERA
+            .render("diff.html", &ctx)
+            .expect("Failed to render diff")
+    } else {
+        let color = output_path.is_none()
+            && env::var_os("NO_COLOR").is_none()
+            && atty::is(atty::Stream::Stdout);
+        diff::render_text(&tools_diff, color, true)
+    };
+
+    match output_path {
+        Some(p) => write_file(p, &s)?,
+        None => print!("{}", &s),
+    };
+    Ok(())
+}
+
+fn main() {
+    let code = run().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        e.exit_code()
+    });
+    std::process::exit(code);
+}
+
+// What an analysis would do, to check config files and CI setups without
+// spending minutes of container time
+fn print_plan(analysis: &Analysis, config_path: Option<&str>, config: &str) {
+    println!("config file: {}", config_path.unwrap_or("none, using defaults"));
+    if !config.trim().is_empty() {
+        for line in config.trim_end().lines() {
+            println!("    {}", line);
+        }
+    }
+    let backends = analysis.backends();
+    match backends.runtime {
+        Some(ref r) => println!("runtime: {} ({})", r.name(), r.binary.display()),
+        None => println!("runtime: none"),
+    }
+    println!("contracts: {}", analysis.inputs().join(", "));
+    println!("tools: {}", analysis.tools().join(", "));
+    println!("depth: {}", format!("{:?}", analysis.depth()).to_lowercase());
+    println!("mythril options: {}", analysis.mythril_options().args().join(" "));
+    println!("oyente options: {}", analysis.oyente_options().args().join(" "));
+    match backends.timeout {
+        Some(t) => println!("timeout: {}s per tool run", t),
+        None => println!("timeout: none"),
+    }
+    // the memory could not be measured when e.g. the daemon is unreachable
+    match backends.runtime.as_ref().and_then(|r| r.memory()) {
+        Some(_) => println!(
+            "jobs: {} ({} at once given the memory available to containers)",
+            analysis.jobs(),
+            analysis.workers()
+        ),
+        None => println!("jobs: {}", analysis.jobs()),
+    }
+    match analysis.cache() {
+        Some(c) => println!("cache: {}", c.dir().display()),
+        None => println!("cache: disabled"),
+    }
+
+    let plan = analysis.plan();
+    println!("images:");
+    let mut tools_in_plan: Vec<&str> = vec![];
+    for invocation in &plan {
+        if !tools_in_plan.contains(&invocation.tool.as_str()) {
+            tools_in_plan.push(&invocation.tool);
+        }
+    }
+    for tool in tools_in_plan {
+        let info = backends.tool_info(tool);
+        let missing = match backends.runtime {
+            Some(ref r) if !backends.is_native(tool) => r.image_info(tool).is_none(),
+            _ => false,
+        };
+        println!(
+            "    {:<20}{:<8}{}{}",
+            tool,
+            info.backend,
+            info.image.unwrap_or_default(),
+            if missing { " (not available locally, see --preload)" } else { "" }
+        );
+    }
+    println!("commands:");
+    for invocation in &plan {
+        println!("    {} {}", invocation.tool, invocation.contract);
+        match invocation.command {
+            Ok(ref c) => println!("        {}", c),
+            Err(ref e) => println!("        not run: {}", e),
         }
     }
 }
 
+// Findings take precedence, failed tools only decide the code when no tool
+// left findings: solc reports compilation errors by failing
+fn exit_code(tool_findings: &[findings::ToolFindings], ignore_tool_errors: bool) -> i32 {
+    if tool_findings.iter().any(|t| !t.findings.is_empty()) {
+        return error::EXIT_FINDINGS;
+    }
+    let errors = tool_findings
+        .iter()
+        .filter(|t| t.status == findings::ToolStatus::Failed)
+        .collect::<Vec<_>>();
+    if errors.is_empty() || ignore_tool_errors {
+        error::EXIT_CLEAN
+    } else if errors
+        .iter()
+        .all(|t| matches!(t.error, Some(SolsaError::Timeout { .. })))
+    {
+        error::EXIT_TIMEOUT
+    } else {
+        error::EXIT_TOOL_ERROR
+    }
+}
+
+// Settings of an analysis, for the analysis itself and for `watch`
+fn analysis_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("tools")
+            .help("Tools to run, all of them by default")
+            .long("tools")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .possible_values(analysis::TOOLS),
+        Arg::with_name("skip-tools")
+            .help("Tools not to run, e.g. one known to crash on the contract")
+            .long("skip-tools")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .possible_values(analysis::TOOLS),
+        Arg::with_name("timeout")
+            .help("Seconds a single tool run may take before it is stopped and reported as timed out")
+            .long("timeout")
+            .takes_value(true)
+            .validator(is_number::<u64>),
+        Arg::with_name("mythril-max-depth")
+            .help("Maximum depth of mythril's symbolic execution, instead of the one --depth gives")
+            .long("mythril-max-depth")
+            .takes_value(true)
+            .validator(is_number::<u32>),
+        Arg::with_name("mythril-execution-timeout")
+            .help("Seconds mythril may spend on symbolic execution")
+            .long("mythril-execution-timeout")
+            .takes_value(true)
+            .validator(is_number::<u64>),
+        Arg::with_name("mythril-transaction-count")
+            .help("Number of transactions mythril analyzes in sequence")
+            .long("mythril-transaction-count")
+            .takes_value(true)
+            .validator(is_number::<u32>),
+        Arg::with_name("mythril-modules")
+            .help("Mythril detection modules to run, all of them by default")
+            .long("mythril-modules")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .possible_values(tools::MYTHRIL_MODULES),
+        Arg::with_name("oyente-depth-limit")
+            .help("Maximum depth of oyente's symbolic execution, instead of the one --depth gives")
+            .long("oyente-depth-limit")
+            .takes_value(true)
+            .validator(is_number::<u32>),
+        Arg::with_name("oyente-gas-limit")
+            .help("Gas limit of oyente's symbolic execution")
+            .long("oyente-gas-limit")
+            .takes_value(true)
+            .validator(is_number::<u64>),
+        Arg::with_name("oyente-loop-limit")
+            .help("Times oyente follows a loop")
+            .long("oyente-loop-limit")
+            .takes_value(true)
+            .validator(is_number::<u32>),
+        Arg::with_name("oyente-solver-timeout")
+            .help("Milliseconds oyente's solver may take on a single query")
+            .long("oyente-solver-timeout")
+            .takes_value(true)
+            .validator(is_number::<u64>),
+        Arg::with_name("oyente-global-timeout")
+            .help("Seconds oyente may spend on symbolic execution")
+            .long("oyente-global-timeout")
+            .takes_value(true)
+            .validator(is_number::<u64>),
+    ]
+}
+
+// Command line settings of an analysis take precedence over the config file
+fn apply_analysis_args(config: &mut config::Config, matches: &ArgMatches) {
+    if let Some(tools) = matches.values_of("tools") {
+        config.tools = Some(tools.map(|t| t.to_owned()).collect());
+    }
+    if let Some(tools) = matches.values_of("skip-tools") {
+        config.skip_tools = tools.map(|t| t.to_owned()).collect();
+    }
+    if let Some(t) = matches.value_of("timeout") {
+        config.timeout = t.parse().ok();
+    }
+    config.mythril.max_depth = number(matches, "mythril-max-depth").or(config.mythril.max_depth);
+    config.mythril.execution_timeout =
+        number(matches, "mythril-execution-timeout").or(config.mythril.execution_timeout);
+    config.mythril.transaction_count =
+        number(matches, "mythril-transaction-count").or(config.mythril.transaction_count);
+    if let Some(modules) = matches.values_of("mythril-modules") {
+        config.mythril.modules = modules.map(|m| m.to_owned()).collect();
+    }
+    config.oyente.depth_limit = number(matches, "oyente-depth-limit").or(config.oyente.depth_limit);
+    config.oyente.gas_limit = number(matches, "oyente-gas-limit").or(config.oyente.gas_limit);
+    config.oyente.loop_limit = number(matches, "oyente-loop-limit").or(config.oyente.loop_limit);
+    config.oyente.solver_timeout =
+        number(matches, "oyente-solver-timeout").or(config.oyente.solver_timeout);
+    config.oyente.global_timeout =
+        number(matches, "oyente-global-timeout").or(config.oyente.global_timeout);
+}
+
 // in time `cyclomatic_complexity` should go back to warn
 #[cfg_attr(feature = "cargo-clippy", allow(single_match, cyclomatic_complexity))]
-fn main() {
+fn run() -> Result<i32, SolsaError> {
     let matches = App::new("solsa")
         .version(crate_version!())
         .about("Aggregates static analysis tooling for ethereum smart contracts.")
         .author("Enhanced Society")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            Arg::with_name("config")
+                .help("Config file to read settings from, solsa.toml when present otherwise")
+                .long("config")
+                .short("c")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("native")
+                .help("Tools to run from the host PATH instead of their container image")
+                .long("native")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .possible_values(backend::NATIVE_TOOLS)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("no-sandbox")
+                .help("Run tool containers with network access, default privileges and a writable root")
+                .long("no-sandbox")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .help("Log each tool's command line and how it exited, -vv adds its working directory and environment")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .help("Format of log lines on stderr, json gives one object per line")
+                .long("log-format")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("cache")
+                .about("Manages the cache of tool results")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(SubCommand::with_name("clean").about("Removes all cached results")),
+        )
+        .subcommand(
+            SubCommand::with_name("images")
+                .about("Manages the tool images")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("build")
+                        .about("Builds tool images from the bundled Dockerfiles, tagged so solsa uses them")
+                        .arg(
+                            Arg::with_name("tool")
+                                .help("Tools to build images for, all of them by default")
+                                .multiple(true)
+                                .possible_values(images::TOOLS),
+                        )
+                        .arg(
+                            Arg::with_name("build-arg")
+                                .help("Build argument passed on to every build, e.g. SOLC_VERSION=0.4.24")
+                                .long("build-arg")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1),
+                        )
+                        .arg(
+                            Arg::with_name("dockerfiles")
+                                .help("Directory with Dockerfile.<tool> files to build from instead, e.g. for tool forks")
+                                .long("dockerfiles")
+                                .takes_value(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about("Saves every tool image, with a manifest, into a single archive")
+                        .arg(
+                            Arg::with_name("tarball")
+                                .help("Archive to write")
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("import")
+                        .about("Loads the tool images from an archive made with export, and verifies them")
+                        .arg(
+                            Arg::with_name("tarball")
+                                .help("Archive to load")
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Checks that everything solsa needs is in place, and how to fix what is not"),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Re-runs the analysis whenever the contract or its imports change")
+                .arg(
+                    Arg::with_name("contract-file")
+                        .short("f")
+                        .long("contract-file")
+                        .takes_value(true)
+                        .help("Path to Solidity smart contract")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .help("Depth of analysis, the deeper the more thorough, but also the slower")
+                        .long("depth")
+                        .short("d")
+                        .possible_values(&["shallow", "deep", "deeper", "deepest"])
+                        .default_value("shallow"),
+                )
+                .arg(
+                    Arg::with_name("no-cache")
+                        .help("Run every tool, instead of reusing results cached from previous runs")
+                        .long("no-cache"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .help("File to keep the html report updated in")
+                        .default_value("index.html")
+                        .takes_value(true),
+                )
+                .args(&analysis_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Compares two JSON reports, listing new, fixed and unchanged findings")
+                .arg(
+                    Arg::with_name("old")
+                        .help("JSON report to compare against, e.g. from the base branch")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .help("JSON report with the changes under review")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .help("Output the diff as JSON")
+                        .long("json"),
+                )
+                .arg(
+                    Arg::with_name("html")
+                        .help("Output the diff as an html file")
+                        .long("html"),
+                )
+                .group(ArgGroup::with_name("output-format").args(&["json", "html"]))
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .help("File to write the diff into")
+                        .takes_value(true),
+                ),
+        )
+        .args(&analysis_args())
         .arg(
             Arg::with_name("contract-file")
                 .short("f")
                 .long("contract-file")
                 .takes_value(true)
-                .help("Path to Solidity smart contract")
+                .multiple(true)
+                .help("Path to Solidity smart contract, can be given more than once")
                 .required(true),
         )
+        .arg(
+            Arg::with_name("jobs")
+                .help("Number of tool runs to have going at once, lowered if the container runtime has too little memory for them")
+                .long("jobs")
+                .short("j")
+                .takes_value(true)
+                .default_value("2")
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err("must be a positive number".to_owned()),
+                }),
+        )
         .arg(
             Arg::with_name("include-source")
                 .help("Include contract sources in report")
@@ -104,6 +527,26 @@ fn main() {
                 .help("Output the report as JSON")
                 .long("json"),
         )
+        .arg(
+            Arg::with_name("markdown")
+                .help("Output the report as GitHub flavoured Markdown, e.g. for pull request comments")
+                .long("markdown"),
+        )
+        .arg(
+            Arg::with_name("text")
+                .help("Output the report as compiler style text, colored when printed to a terminal")
+                .long("text"),
+        )
+        .arg(
+            Arg::with_name("gitlab")
+                .help("Output the report as GitLab Code Quality JSON")
+                .long("gitlab"),
+        )
+        .arg(
+            Arg::with_name("checkstyle")
+                .help("Output the report as Checkstyle XML")
+                .long("checkstyle"),
+        )
         .arg(
             Arg::with_name("silent")
                 .help("Do not output the report, but only basic pass/fail info")
@@ -111,14 +554,21 @@ fn main() {
         )
         .group(
             ArgGroup::with_name("output-format")
-                .args(&["html", "json", "silent"])
+                .args(&[
+                    "html",
+                    "json",
+                    "markdown",
+                    "text",
+                    "gitlab",
+                    "checkstyle",
+                    "silent",
+                ])
                 .multiple(false),
         )
         .arg(
             Arg::with_name("error-exit")
-                .help("Exit with error code if issues are found")
-                .long("error-exit")
-                .requires("silent"),
+                .help("Exit with a non-zero code when issues are found or a tool fails (see README for the codes)")
+                .long("error-exit"),
         )
         .arg(
             Arg::with_name("preload")
@@ -126,6 +576,21 @@ fn main() {
                 .long("preload")
                 .short("p"),
         )
+        .arg(
+            Arg::with_name("dry-run")
+                .help("Print the settings, tools, images and commands of the analysis, without running it")
+                .long("dry-run"),
+        )
+        .arg(
+            Arg::with_name("keep-raw")
+                .help("Save the raw output of each tool next to the report, in <report>.raw (solsa-raw when printing the report)")
+                .long("keep-raw"),
+        )
+        .arg(
+            Arg::with_name("no-cache")
+                .help("Run every tool, instead of reusing results cached from previous runs")
+                .long("no-cache"),
+        )
         .arg(
             Arg::with_name("depth")
                 .help("Depth of analysis, the deeper the more thorough, but also the slower")
@@ -134,6 +599,41 @@ fn main() {
                 .possible_values(&["shallow", "deep", "deeper", "deepest"])
                 .default_value("shallow"),
         )
+        .arg(
+            Arg::with_name("template")
+                .help("Tera template to render the html report with, instead of the built-in one")
+                .long("template")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    "json",
+                    "markdown",
+                    "text",
+                    "gitlab",
+                    "checkstyle",
+                    "silent",
+                ]),
+        )
+        .arg(
+            Arg::with_name("template-dir")
+                .help("Directory of Tera templates (layouts, partials) available to the report template")
+                .long("template-dir")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    "json",
+                    "markdown",
+                    "text",
+                    "gitlab",
+                    "checkstyle",
+                    "silent",
+                ]),
+        )
+        .arg(
+            Arg::with_name("link-base")
+                .help("Base URL findings link their file:line to in the markdown report (e.g. https://github.com/<org>/<repo>/blob/<commit>)")
+                .long("link-base")
+                .takes_value(true)
+                .requires("markdown"),
+        )
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -141,203 +641,415 @@ fn main() {
                 .conflicts_with("silent")
                 .takes_value(true),
         )
-        .get_matches();
-
-    docker_check(matches.is_present("preload"));
+        .get_matches_safe()
+        .unwrap_or_else(|e| {
+            if e.use_stderr() {
+                eprintln!("{}", e.message);
+                std::process::exit(error::EXIT_USAGE);
+            }
+            // --help and --version
+            e.exit()
+        });
+    let verbosity = matches.occurrences_of("verbose");
+    logger::init(verbosity, matches.value_of("log-format") == Some("json"));
 
-    let contract_path: String = matches
-        .value_of("contract-file")
-        .expect("Contract file is required")
-        .to_owned();
+    if let Some(m) = matches.subcommand_matches("diff") {
+        return diff_command(m).map(|_| error::EXIT_CLEAN);
+    }
+    if let Some(m) = matches.subcommand_matches("cache") {
+        if m.subcommand_matches("clean").is_some() {
+            if let Some(c) = cache::Cache::open() {
+                c.clean()
+                    .map_err(|e| SolsaError::io(&c.dir().display().to_string(), &e))?;
+            }
+        }
+        return Ok(error::EXIT_CLEAN);
+    }
 
-    let include_source = matches.is_present("include-source");
+    let mut config = config::Config::load(matches.value_of("config"))?;
+    if let Some(tools) = matches.values_of("native") {
+        config.native = tools.map(|t| t.to_owned()).collect();
+    }
+    if matches.is_present("no-sandbox") {
+        config.sandbox.enabled = false;
+    }
+    if matches.subcommand_matches("doctor").is_some() {
+        return Ok(if doctor::doctor(&config, &config.native) {
+            error::EXIT_CLEAN
+        } else {
+            error::EXIT_TOOL_ERROR
+        });
+    }
+    if let Some(m) = matches.subcommand_matches("images") {
+        let backends = backend::Backends::resolve(&config, &[])?;
+        let runtime = backends.runtime.as_ref().ok_or_else(|| SolsaError::Spawn {
+            tool: "container runtime".to_owned(),
+            message: "images are built, exported and imported with a container runtime, \
+                      run `solsa doctor` for details"
+                .to_owned(),
+        })?;
+        if let Some(m) = m.subcommand_matches("build") {
+            let tools = match m.values_of("tool") {
+                Some(t) => t.collect(),
+                None => images::TOOLS.to_vec(),
+            };
+            let build_args = m.values_of("build-arg")
+                .map(|a| a.collect::<Vec<_>>())
+                .unwrap_or_default();
+            images::build(runtime, &tools, &build_args, m.value_of("dockerfiles"))?;
+        }
+        if let Some(m) = m.subcommand_matches("export") {
+            images::export(runtime, m.value_of("tarball").expect("Archive is required"))?;
+        }
+        if let Some(m) = m.subcommand_matches("import") {
+            images::import(runtime, m.value_of("tarball").expect("Archive is required"))?;
+        }
+        return Ok(error::EXIT_CLEAN);
+    }
+    let ignore_tool_errors = config.ignore_tool_errors;
 
-    let mut contents = String::new();
-    if include_source {
-        let mut cmd = docker_cmd!("solidity-flattener");
-        contents = cmd.arg(&contract_path)
-            .output()
-            .ok()
-            .and_then(|output| {
-                if output.status.success() {
-                    String::from_utf8(output.stdout).ok()
-                } else {
-                    None
-                }
-            })
-            .expect("Failed to read contract");
+    if let Some(m) = matches.subcommand_matches("watch") {
+        apply_analysis_args(&mut config, m);
+        let analysis = Analysis::builder()
+            .config(config)
+            .depth(parse_depth(m.value_of("depth")))
+            .cache(!m.is_present("no-cache"))
+            .preload(matches.is_present("preload"))
+            .build()?;
+        let color = env::var_os("NO_COLOR").is_none() && atty::is(atty::Stream::Stdout);
+        watch::watch(
+            analysis,
+            m.value_of("contract-file").expect("Contract file is required"),
+            m.value_of("output").unwrap_or("index.html"),
+            color,
+        );
+        return Ok(error::EXIT_CLEAN);
     }
 
+    apply_analysis_args(&mut config, &matches);
+    let contract_paths = matches
+        .values_of("contract-file")
+        .expect("Contract file is required")
+        .collect::<Vec<_>>();
+
     let output_format = if matches.is_present("output-format") {
-        match (
-            matches.is_present("html"),
-            matches.is_present("json"),
-            matches.is_present("silent"),
-        ) {
-            (_, false, false) => OutputType::HTML,
-            (false, true, false) => OutputType::JSON,
-            (false, false, true) => OutputType::None,
-            (_, _, _) => panic!("Only ONE output format can be chosen"),
+        if matches.is_present("json") {
+            OutputType::JSON
+        } else if matches.is_present("markdown") {
+            OutputType::Markdown
+        } else if matches.is_present("text") {
+            OutputType::Text
+        } else if matches.is_present("gitlab") {
+            OutputType::GitLab
+        } else if matches.is_present("checkstyle") {
+            OutputType::Checkstyle
+        } else if matches.is_present("silent") {
+            OutputType::None
+        } else {
+            OutputType::HTML
         }
     } else {
-        // default output_format
-        OutputType::HTML
-    };
-
-    let analysis_depth = match matches.value_of("depth").unwrap_or("shallow") {
-        "deepest" => tools::AnalysisDepth::Deepest,
-        "deeper" => tools::AnalysisDepth::Deeper,
-        "deep" => tools::AnalysisDepth::Deep,
-        "shallow" | _ => tools::AnalysisDepth::Shallow,
+        // default output_format, can be changed for those who would rather
+        // not pass the same flag on every invocation
+        match env::var("SOLSA_FORMAT").unwrap_or_default().as_str() {
+            "json" => OutputType::JSON,
+            "markdown" => OutputType::Markdown,
+            "text" => OutputType::Text,
+            "gitlab" => OutputType::GitLab,
+            "checkstyle" => OutputType::Checkstyle,
+            "silent" => OutputType::None,
+            "" | "html" => OutputType::HTML,
+            other => {
+                return Err(SolsaError::config(format!(
+                    "Invalid SOLSA_FORMAT {}, expected one of html, json, markdown, text, gitlab, checkstyle or silent",
+                    other
+                )))
+            }
+        }
     };
 
-    // very fast to complete, the penalty to run in parallel is unnecesary
-    let solc_out = tools::run_solc(&contract_path);
-    let solium_out = tools::run_solium(&contract_path);
-
-    // slower tools gain a bit by running in parallel
-
-    let cp_arc = Arc::new(contract_path);
-    let cp_arc_myth = cp_arc.clone();
-    let cp_arc_oyente = cp_arc_myth.clone();
-
-    let myth_handle =
-        thread::spawn(move || tools::run_mythril(cp_arc_myth.as_ref(), analysis_depth));
+    let include_source = matches.is_present("include-source");
+    let dry_run = matches.is_present("dry-run");
+    let config_toml = config.to_toml();
+    let mut builder = Analysis::builder()
+        .config(config)
+        .inputs(contract_paths.iter().cloned())
+        .depth(parse_depth(matches.value_of("depth")))
+        .cache(!matches.is_present("no-cache"))
+        .include_source(include_source)
+        .jobs(matches.value_of("jobs").and_then(|j| j.parse().ok()).unwrap_or(2))
+        .preload(matches.is_present("preload") && !dry_run)
+        .verify_images(!dry_run);
+    if matches.is_present("keep-raw") {
+        let report_path = match (matches.value_of("output"), &output_format) {
+            (Some(p), _) => Some(p),
+            (None, &OutputType::HTML) => Some("index.html"),
+            (None, _) => None,
+        };
+        builder = builder.keep_raw(match report_path {
+            Some(p) => Path::new(p).with_extension("raw"),
+            None => PathBuf::from("solsa-raw"),
+        });
+    }
+    let analysis = builder.build()?;
+    if dry_run {
+        print_plan(
+            &analysis,
+            config::Config::path(matches.value_of("config")),
+            &config_toml,
+        );
+        return Ok(error::EXIT_CLEAN);
+    }
 
-    let oyente_handle =
-        thread::spawn(move || tools::run_oyente(cp_arc_oyente.as_ref(), analysis_depth));
+    // an html page, or a user template, describes a single contract
+    if output_format == OutputType::HTML && contract_paths.len() > 1 {
+        return Err(SolsaError::config(
+            "An html report takes a single contract, use --json, --markdown, --text, --gitlab or --checkstyle for several",
+        ));
+    }
 
-    let myth_out = myth_handle.join().expect("Failed to run mythril");
-    let oyente_out = oyente_handle.join().expect("Failed to run oyente");
+    // stdout may well be the report, progress goes to stderr
+    let progress = if output_format == OutputType::None {
+        None
+    } else {
+        // a status line being redrawn would garble log lines
+        Some(progress::ProgressDisplay::new(
+            verbosity == 0 && atty::is(atty::Stream::Stderr),
+        ))
+    };
+    let results = analysis.run_with_progress(|p| {
+        if let Some(ref progress) = progress {
+            progress.update(p);
+        }
+    });
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    let results = results?;
+    let tool_findings = results
+        .iter()
+        .flat_map(|r| r.findings.iter().cloned())
+        .collect::<Vec<_>>();
 
     match output_format {
         OutputType::HTML => {
-            let mut ctx = Context::new();
-            ctx.add("solsa_version", crate_version!());
-            ctx.add("contract_file", cp_arc.as_ref());
-            if include_source {
-                ctx.add("source", &contents);
-            }
-            match solc_out {
-                Some(s) => match s {
-                    tools::SolcResponse::Success(j) => ctx.add("solc_out", &j),
-                    tools::SolcResponse::Failure(s) => ctx.add("solc_err", &s),
-                },
-                _ => (),
-            }
-            match solium_out {
-                Some(s) => match s {
-                    tools::SoliumResponse::Success(j) => ctx.add("solium_out", &j),
-                    tools::SoliumResponse::Failure(s) => ctx.add("solium_err", &s),
-                },
-                _ => (),
-            }
-            match myth_out {
-                Some(s) => match s {
-                    tools::MythrilResponse::Success(j) => ctx.add("myth_out", &j),
-                    tools::MythrilResponse::Failure(s) => ctx.add("myth_err", &s),
-                },
-                _ => (),
-            }
-            match oyente_out {
-                Some(s) => match s {
-                    tools::OyenteResponse::Success(j, b) => {
-                        ctx.add("oyente_out", &j);
-                        ctx.add("oyente_issues", &b)
-                    }
-                    tools::OyenteResponse::Failure(s) => ctx.add("oyente_err", &s),
-                },
-                _ => (),
-            }
+            let result = &results[0];
+            let ctx = report::report_context(
+                &result.contract,
+                result.source.as_deref(),
+                &result.findings,
+                &result.responses,
+                &analysis.tool_infos(),
+            );
 
-            let idx = TERA.render("index.html", &ctx)
-                .expect("Failed to render reports");
+            let (tera, template_name) = report::report_templates(
+                matches.value_of("template"),
+                matches.value_of("template-dir"),
+            )?;
+            // user templates can fail to render, e.g. on an undefined variable
+            let idx = tera.render(&template_name, &ctx).map_err(|e| {
+                SolsaError::config(format!("Failed to render {}: {}", template_name, e))
+            })?;
 
             let output_path = matches.value_of("output").unwrap_or("index.html");
-            fs::write(&output_path, &idx).expect("Unable to write file");
+            write_file(output_path, &idx)?;
         }
-        OutputType::JSON => {
-            let all_encompassing_json_monstruosity = json!({
-                "solc" : match solc_out {
-                    Some(tools::SolcResponse::Success(s)) =>
-                    json!({"error": false, "result": s}),
-                    Some(tools::SolcResponse::Failure(s)) =>
-                     json!({"error": true, "result": s}),
-                    None => json!({"error": false, "result": ""}),
-                },
-                "solium" : match solium_out {
-                    Some(tools::SoliumResponse::Success(s)) =>
-                    json!({"error": false, "result": s}),
-                    Some(tools::SoliumResponse::Failure(s)) =>
-                    json!({"error": true, "result": s}),
-                    None => json!({"error": false, "result": ""}),
-                },
-                "mythril" : match myth_out {
-                    Some(tools::MythrilResponse::Success(s)) =>
-                    json!({"error": false, "result": s}),
-                    Some(tools::MythrilResponse::Failure(s)) =>
-                     json!({"error": true, "result": s}),
-                    None => json!({"error": false, "result": ""}),
-                },
-                "oyente" : match oyente_out {
-                    Some(tools::OyenteResponse::Success(s, _)) =>
-                    json!({"error": false, "result": s}),
-                    Some(tools::OyenteResponse::Failure(s)) =>
-                    json!({"error": true, "result": s}),
-                    None => json!({"error": false, "result": ""}),
-                },
-                "source": if include_source { Some(&contents) } else { None },
-                "solsa_version": crate_version!()
-            });
-
-            let s = serde_json::to_string_pretty(&all_encompassing_json_monstruosity)
-                .expect("Failed to serialize report");
+        OutputType::Markdown => {
+            let tool_infos = analysis.tool_infos();
+            let md = results
+                .iter()
+                .map(|result| {
+                    let mut ctx = report::report_context(
+                        &result.contract,
+                        result.source.as_deref(),
+                        &result.findings,
+                        &result.responses,
+                        &tool_infos,
+                    );
+                    if let Some(link_base) = matches.value_of("link-base") {
+                        ctx.add("link_base", &link_base.trim_end_matches('/'));
+                    }
+                    report::TERA
+                        .render("report.md", &ctx)
+                        .expect("Failed to render reports")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
 
+            match matches.value_of("output") {
+                Some(p) => write_file(p, &md)?,
+                None => println!("{}", &md),
+            };
+        }
+        OutputType::Text => {
             let output_path = matches.value_of("output");
+            // https://no-color.org/
+            let color = output_path.is_none()
+                && env::var_os("NO_COLOR").is_none()
+                && atty::is(atty::Stream::Stdout);
+            let txt = report::render_text(&results, color);
             match output_path {
-                Some(p) => {
-                    fs::write(&p, &s).expect("Unable to write file");
-                }
-                None => println!("{}", &s),
+                Some(p) => write_file(p, &txt)?,
+                None => print!("{}", &txt),
             };
         }
-        OutputType::None => {
-            let mut tools_with_issues = Vec::new();
-            match solc_out {
-                Some(tools::SolcResponse::Failure(_)) => tools_with_issues.push("solc"),
-                None => tools_with_issues.push("solc"),
-                _ => {}
-            };
-            match solium_out {
-                Some(tools::SoliumResponse::Success(ref l)) if !l.is_empty() => {
-                    tools_with_issues.push("solium")
-                }
-                Some(tools::SoliumResponse::Failure(_)) => tools_with_issues.push("solium"),
-                None => tools_with_issues.push("solium"),
-                _ => {}
+        OutputType::GitLab | OutputType::Checkstyle => {
+            let s = match output_format {
+                OutputType::GitLab => report::render_gitlab(&results),
+                _ => report::render_checkstyle(&results),
             };
-            match myth_out {
-                Some(tools::MythrilResponse::Success(ref o)) if !o.success => {
-                    tools_with_issues.push("solium")
-                }
-                Some(tools::MythrilResponse::Failure(_)) => tools_with_issues.push("mythril"),
-                None => tools_with_issues.push("mythril"),
-                _ => {}
+            match matches.value_of("output") {
+                Some(p) => write_file(p, &s)?,
+                None => println!("{}", &s),
             };
-            match oyente_out {
-                Some(tools::OyenteResponse::Success(_, true)) => tools_with_issues.push("oyente"),
-                Some(tools::OyenteResponse::Failure(_)) => tools_with_issues.push("oyente"),
-                None => tools_with_issues.push("oyente"),
-                _ => {}
+        }
+        OutputType::JSON => {
+            let tool_infos = analysis.tool_infos();
+            let mut json_reports = results
+                .into_iter()
+                .map(|result| {
+                    report::JsonReport::new(
+                        &result.contract,
+                        result.source,
+                        result.responses,
+                        tool_infos.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            // a single contract keeps the report format `diff` reads
+            let s = if json_reports.len() == 1 {
+                serde_json::to_string_pretty(&json_reports.remove(0))
+            } else {
+                serde_json::to_string_pretty(&json_reports)
+            }.expect("Failed to serialize report");
+
+            let output_path = matches.value_of("output");
+            match output_path {
+                Some(p) => write_file(p, &s)?,
+                None => println!("{}", &s),
             };
+        }
+        OutputType::None => {
+            // a tool that failed to run found nothing, which is not the same
+            // as the contract being clean. Compilation errors are findings
+            // of a failed solc though. Tools left out were not asked to.
+            let (tools_with_issues, failed): (Vec<_>, Vec<_>) = results
+                .iter()
+                .flat_map(|r| r.findings.iter().map(move |t| (r.contract.as_str(), t)))
+                .filter(|&(_, t)| match t.status {
+                    findings::ToolStatus::Issues | findings::ToolStatus::Failed => true,
+                    findings::ToolStatus::Ok | findings::ToolStatus::NotRun => false,
+                })
+                .partition(|&(_, t)| !t.findings.is_empty());
             if tools_with_issues.is_empty() {
                 println!("No issues found");
             } else {
-                println!("Issues found in {}", tools_with_issues.join(", "));
-                if matches.is_present("error-exit") {
-                    std::process::exit(1);
+                let mut names: Vec<&str> = vec![];
+                for &(_, t) in &tools_with_issues {
+                    if !names.contains(&t.tool.as_str()) {
+                        names.push(&t.tool);
+                    }
                 }
+                println!("Issues found in {}", names.join(", "));
             }
+            for &(contract, t) in &failed {
+                let tool = if results.len() > 1 {
+                    format!("{} on {}", t.tool, contract)
+                } else {
+                    t.tool.clone()
+                };
+                match t.error {
+                    Some(ref e) => eprintln!("{} failed to run: {}", tool, e.summary()),
+                    None => eprintln!("{} failed to run", tool),
+                }
+            }
+        }
+    }
+
+    if matches.is_present("error-exit") {
+        Ok(exit_code(&tool_findings, ignore_tool_errors))
+    } else {
+        Ok(error::EXIT_CLEAN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use findings::{Finding, Severity, ToolFindings, ToolStatus};
+
+    fn tool(status: ToolStatus, findings: usize, error: Option<SolsaError>) -> ToolFindings {
+        let finding = Finding {
+            tool: "solium".to_owned(),
+            severity: Severity::Warning,
+            filename: "A.sol".to_owned(),
+            line: 3,
+            column: 1,
+            contract: "A.sol".to_owned(),
+            rule: "warning".to_owned(),
+            message: "Avoid using 'now'.".to_owned(),
+            details: None,
+        };
+        ToolFindings {
+            tool: "solium".to_owned(),
+            status,
+            findings: vec![finding; findings],
+            error,
         }
     }
+
+    fn timeout() -> Option<SolsaError> {
+        Some(SolsaError::Timeout {
+            tool: "mythril".to_owned(),
+            seconds: 600,
+        })
+    }
+
+    fn crash() -> Option<SolsaError> {
+        Some(SolsaError::Crash {
+            tool: "oyente".to_owned(),
+            status: Some(1),
+            output: String::new(),
+        })
+    }
+
+    #[test]
+    fn clean_analysis() {
+        let tools = vec![tool(ToolStatus::Ok, 0, None), tool(ToolStatus::NotRun, 0, None)];
+        assert_eq!(exit_code(&tools, false), error::EXIT_CLEAN);
+    }
+
+    #[test]
+    fn findings_found() {
+        let tools = vec![tool(ToolStatus::Ok, 0, None), tool(ToolStatus::Issues, 2, None)];
+        assert_eq!(exit_code(&tools, false), error::EXIT_FINDINGS);
+    }
+
+    #[test]
+    fn tool_failure() {
+        let tools = vec![tool(ToolStatus::Ok, 0, None), tool(ToolStatus::Failed, 0, crash())];
+        assert_eq!(exit_code(&tools, false), error::EXIT_TOOL_ERROR);
+        assert_eq!(exit_code(&tools, true), error::EXIT_CLEAN);
+    }
+
+    #[test]
+    fn timeout_only_when_every_failure_timed_out() {
+        let timed_out = vec![tool(ToolStatus::Ok, 0, None), tool(ToolStatus::Failed, 0, timeout())];
+        assert_eq!(exit_code(&timed_out, false), error::EXIT_TIMEOUT);
+        let mixed = vec![
+            tool(ToolStatus::Failed, 0, timeout()),
+            tool(ToolStatus::Failed, 0, crash()),
+        ];
+        assert_eq!(exit_code(&mixed, false), error::EXIT_TOOL_ERROR);
+    }
+
+    #[test]
+    fn findings_win_over_failed_tools() {
+        let tools = vec![tool(ToolStatus::Issues, 1, None), tool(ToolStatus::Failed, 0, crash())];
+        assert_eq!(exit_code(&tools, false), error::EXIT_FINDINGS);
+        // solc failing on compilation errors, which are its findings
+        let solc = vec![tool(ToolStatus::Failed, 1, crash())];
+        assert_eq!(exit_code(&solc, false), error::EXIT_FINDINGS);
+    }
 }
@@ -0,0 +1,135 @@
+use std::env;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use analysis::Job;
+use scheduler::Progress;
+
+const TICK: Duration = Duration::from_millis(100);
+const SPINNER: &[char] = &['|', '/', '-', '\\'];
+
+struct State {
+    running: Vec<(Job, Instant)>,
+    done: usize,
+    total: usize,
+    frame: usize,
+    stopped: bool,
+}
+
+// Shows which tools are running, and for how long. On a terminal a status
+// line is kept up to date below the finished jobs, anywhere else (e.g. CI
+// logs) each job gets a line when it starts and when it finishes.
+pub struct ProgressDisplay {
+    state: Arc<Mutex<State>>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+fn seconds(d: Duration) -> String {
+    format!("{}.{}s", d.as_secs(), d.subsec_millis() / 100)
+}
+
+fn clear_line<W: Write>(out: &mut W) {
+    let _ = write!(out, "\r\x1b[K");
+}
+
+fn draw<W: Write>(state: &State, out: &mut W) {
+    let running = state
+        .running
+        .iter()
+        .map(|&(ref job, start)| format!("{} {} {}s", job.tool, job.contract, start.elapsed().as_secs()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let line = format!(
+        "{} [{}/{}] {}",
+        SPINNER[state.frame % SPINNER.len()],
+        state.done,
+        state.total,
+        running
+    );
+    // a line wider than the terminal wraps, and \r no longer gets back to its start
+    let width = env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse::<usize>().ok())
+        .unwrap_or(80);
+    clear_line(out);
+    let _ = write!(out, "{}", line.chars().take(width.saturating_sub(1)).collect::<String>());
+    let _ = out.flush();
+}
+
+impl ProgressDisplay {
+    pub fn new(live: bool) -> ProgressDisplay {
+        let state = Arc::new(Mutex::new(State {
+            running: Vec::new(),
+            done: 0,
+            total: 0,
+            frame: 0,
+            stopped: !live,
+        }));
+        let ticker = if live {
+            let state = state.clone();
+            Some(thread::spawn(move || loop {
+                thread::sleep(TICK);
+                let mut state = state.lock().expect("Progress state poisoned");
+                if state.stopped {
+                    break;
+                }
+                state.frame += 1;
+                draw(&state, &mut io::stderr());
+            }))
+        } else {
+            None
+        };
+        ProgressDisplay { state, ticker }
+    }
+
+    pub fn update(&self, progress: Progress<Job>) {
+        let live = self.ticker.is_some();
+        let mut state = self.state.lock().expect("Progress state poisoned");
+        let stderr = io::stderr();
+        let mut out = stderr.lock();
+        match progress {
+            Progress::Started { job, total } => {
+                state.running.push((job.clone(), Instant::now()));
+                state.total = total;
+                if !live {
+                    let _ = writeln!(out, "{} {} started", job.tool, job.contract);
+                }
+            }
+            Progress::Finished {
+                job,
+                elapsed,
+                done,
+                total,
+            } => {
+                state.running.retain(|(j, _)| j.tool != job.tool || j.contract != job.contract);
+                state.done = done;
+                if live {
+                    clear_line(&mut out);
+                }
+                let _ = writeln!(
+                    out,
+                    "[{}/{}] {} {} finished in {}",
+                    done,
+                    total,
+                    job.tool,
+                    job.contract,
+                    seconds(elapsed)
+                );
+                if live {
+                    draw(&state, &mut out);
+                }
+            }
+        }
+    }
+
+    // Clears the status line, so it does not end up mixed with the report
+    pub fn finish(mut self) {
+        if let Some(ticker) = self.ticker.take() {
+            self.state.lock().expect("Progress state poisoned").stopped = true;
+            let _ = ticker.join();
+            clear_line(&mut io::stderr());
+        }
+    }
+}
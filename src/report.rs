@@ -0,0 +1,648 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use serde_json;
+use tera::{Context, Tera};
+
+use analysis::ContractResult;
+use backend::ToolInfo;
+use error::SolsaError;
+use findings::{fingerprints, fnv1a, Finding, Severity, ToolFindings, ToolStatus};
+use tool_output;
+use tools;
+
+lazy_static! {
+    pub static ref TERA: Tera = {
+        // Get templates at compile time, remove a runtime dependency
+        let mut tera = Tera::default();
+        tera.add_raw_template("index.html", include_str!("../templates/index.html")).unwrap();
+        tera.add_raw_template("report.md", include_str!("../templates/report.md")).unwrap();
+        tera.add_raw_template("diff.html", include_str!("../templates/diff.html")).unwrap();
+        // user templates may pass anything, which must not bring solsa down
+        tera.register_filter("float", |s, _| {
+            let f = s.as_str()
+                .and_then(|s| s.parse::<f32>().ok())
+                .ok_or_else(|| format!("Filter `float` expects a number as a string, got {}", s))?;
+            serde_json::value::to_value(f).map_err(|e| e.to_string().into())
+        });
+        // multi-line messages break markdown lists and tables
+        tera.register_filter("oneline", |s, _|
+        serde_json::value::to_value(
+            s.as_str().unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ")
+        ).map_err(|e| e.to_string().into()));
+        tera
+    };
+}
+
+// User supplied templates take precedence, the built-in ones remain available
+// as a fallback and to be extended/included from the user's templates
+pub fn report_templates(
+    template: Option<&str>,
+    template_dir: Option<&str>,
+) -> Result<(Tera, String), SolsaError> {
+    let mut tera = match template_dir {
+        Some(dir) => Tera::new(&format!("{}/**/*", dir)).map_err(|e| {
+            SolsaError::config(format!("Failed to load templates from {}: {}", dir, e))
+        })?,
+        None => Tera::default(),
+    };
+    tera.extend(&TERA)
+        .map_err(|e| SolsaError::config(format!("Failed to load templates: {}", e)))?;
+
+    let template_name = match template {
+        Some(t) => {
+            // keep the file name so autoescaping follows the template's extension
+            let name = Path::new(t)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("report.html")
+                .to_owned();
+            tera.add_template_file(t, Some(&name))
+                .map_err(|e| SolsaError::config(format!("Failed to load template {}: {}", t, e)))?;
+            name
+        }
+        None => "index.html".to_owned(),
+    };
+    Ok((tera, template_name))
+}
+
+// Context shared by every template based report (see README for the variables)
+pub fn report_context(
+    contract_file: &str,
+    source: Option<&str>,
+    tool_findings: &[ToolFindings],
+    responses: &tools::ToolResponses,
+    tool_infos: &[ToolInfo],
+) -> Context {
+    let mut all_findings = tool_findings
+        .iter()
+        .flat_map(|t| t.findings.iter())
+        .collect::<Vec<_>>();
+    all_findings.sort_by_key(|f| (f.severity, f.filename.clone(), f.line));
+    let contracts = all_findings
+        .iter()
+        .map(|f| f.contract.as_str())
+        .collect::<BTreeSet<_>>();
+    let count = |sev| all_findings.iter().filter(|f| f.severity == sev).count();
+    let findings_by_severity = [Severity::Error, Severity::Warning, Severity::Info]
+        .iter()
+        .filter(|&&sev| count(sev) > 0)
+        .map(|&sev| {
+            json!({
+                "severity": sev,
+                "findings": all_findings.iter().filter(|f| f.severity == sev).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut ctx = Context::new();
+    ctx.add("solsa_version", crate_version!());
+    ctx.add("contract_file", &contract_file);
+    if let Some(s) = source {
+        ctx.add("source", &s);
+    }
+    // with a one line description of why failed tools failed, for reports
+    // with little room for it, and when and how long each tool ran
+    let tools = tool_findings
+        .iter()
+        .map(|t| {
+            let mut v = json!(t);
+            if let Some(ref e) = t.error {
+                v["error_message"] = json!(e.summary());
+            }
+            if let Some(run) = responses.runs.get(&t.tool) {
+                v["run"] = json!(run);
+            }
+            v
+        })
+        .collect::<Vec<_>>();
+    let tool_infos = tool_infos
+        .iter()
+        .map(|info| {
+            let mut v = json!(info);
+            if let Some(run) = responses.runs.get(&info.tool) {
+                v["run"] = json!(run);
+            }
+            v
+        })
+        .collect::<Vec<_>>();
+    ctx.add("tools", &tools);
+    ctx.add("tool_infos", &tool_infos);
+    ctx.add("findings", &all_findings);
+    ctx.add("findings_by_severity", &findings_by_severity);
+    ctx.add("contracts", &contracts);
+    ctx.add(
+        "severity_counts",
+        &json!({
+            "error": count(Severity::Error),
+            "warning": count(Severity::Warning),
+            "info": count(Severity::Info),
+        }),
+    );
+    match responses.solc {
+        Some(tools::SolcResponse::Success(ref j)) => ctx.add("solc_out", j),
+        Some(tools::SolcResponse::Failure(ref e)) => ctx.add("solc_err", &e.to_string()),
+        None => (),
+    }
+    match responses.solium {
+        Some(tools::SoliumResponse::Success(ref j)) => ctx.add("solium_out", j),
+        Some(tools::SoliumResponse::Failure(ref e)) => ctx.add("solium_err", &e.to_string()),
+        None => (),
+    }
+    match responses.mythril {
+        Some(tools::MythrilResponse::Success(ref j)) => ctx.add("myth_out", j),
+        Some(tools::MythrilResponse::Failure(ref e)) => ctx.add("myth_err", &e.to_string()),
+        None => (),
+    }
+    match responses.oyente {
+        Some(tools::OyenteResponse::Success(ref j, ref b)) => {
+            ctx.add("oyente_out", j);
+            ctx.add("oyente_issues", b)
+        }
+        Some(tools::OyenteResponse::Failure(ref e)) => ctx.add("oyente_err", &e.to_string()),
+        None => (),
+    }
+    ctx
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ToolResult<T> {
+    Output(T),
+    Message(String),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ToolReport<T> {
+    pub error: bool,
+    pub result: ToolResult<T>,
+    // missing from reports of older versions, which only kept the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<SolsaError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<tools::ToolRun>,
+}
+
+impl<T> ToolReport<T> {
+    fn success(output: T) -> ToolReport<T> {
+        ToolReport {
+            error: false,
+            result: ToolResult::Output(output),
+            failure: None,
+            run: None,
+        }
+    }
+
+    fn failure(error: SolsaError) -> ToolReport<T> {
+        ToolReport {
+            error: true,
+            result: ToolResult::Message(error.to_string()),
+            failure: Some(error),
+            run: None,
+        }
+    }
+
+    fn not_run() -> ToolReport<T> {
+        ToolReport {
+            error: false,
+            result: ToolResult::Message("not run".to_owned()),
+            failure: None,
+            run: None,
+        }
+    }
+
+    fn with_run(mut self, run: Option<tools::ToolRun>) -> ToolReport<T> {
+        self.run = run;
+        self
+    }
+
+    fn into_response<R, S, F>(
+        self,
+        tool: &str,
+        runs: &mut BTreeMap<String, tools::ToolRun>,
+        success: S,
+        failure: F,
+    ) -> Option<R>
+    where
+        S: FnOnce(T) -> R,
+        F: FnOnce(SolsaError) -> R,
+    {
+        if let Some(run) = self.run {
+            runs.insert(tool.to_owned(), run);
+        }
+        match self.result {
+            ToolResult::Output(o) => Some(success(o)),
+            ToolResult::Message(s) => match (self.error, self.failure) {
+                (true, Some(e)) => Some(failure(e)),
+                (true, None) => Some(failure(SolsaError::Crash {
+                    tool: tool.to_owned(),
+                    status: None,
+                    output: s,
+                })),
+                (false, _) => None,
+            },
+        }
+    }
+}
+
+/// Layout of the JSON report, which can be read back (e.g. to diff reports)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonReport {
+    #[serde(default)]
+    pub contract_file: Option<String>,
+    pub solc: ToolReport<tool_output::SolcOutput>,
+    pub solium: ToolReport<Vec<tool_output::SoliumIssue>>,
+    pub mythril: ToolReport<tool_output::MythrilOutput>,
+    pub oyente: ToolReport<tool_output::OyenteOutput>,
+    pub source: Option<String>,
+    pub solsa_version: String,
+    #[serde(default)]
+    pub tools: Vec<ToolInfo>,
+}
+
+impl JsonReport {
+    pub fn new(
+        contract_file: &str,
+        source: Option<String>,
+        responses: tools::ToolResponses,
+        tools: Vec<ToolInfo>,
+    ) -> JsonReport {
+        let mut runs = responses.runs;
+        JsonReport {
+            contract_file: Some(contract_file.to_owned()),
+            solc: match responses.solc {
+                Some(tools::SolcResponse::Success(s)) => ToolReport::success(s),
+                Some(tools::SolcResponse::Failure(s)) => ToolReport::failure(s),
+                None => ToolReport::not_run(),
+            }.with_run(runs.remove("solc")),
+            solium: match responses.solium {
+                Some(tools::SoliumResponse::Success(s)) => ToolReport::success(s),
+                Some(tools::SoliumResponse::Failure(s)) => ToolReport::failure(s),
+                None => ToolReport::not_run(),
+            }.with_run(runs.remove("solium")),
+            mythril: match responses.mythril {
+                Some(tools::MythrilResponse::Success(s)) => ToolReport::success(s),
+                Some(tools::MythrilResponse::Failure(s)) => ToolReport::failure(s),
+                None => ToolReport::not_run(),
+            }.with_run(runs.remove("mythril")),
+            oyente: match responses.oyente {
+                Some(tools::OyenteResponse::Success(s, _)) => ToolReport::success(s),
+                Some(tools::OyenteResponse::Failure(s)) => ToolReport::failure(s),
+                None => ToolReport::not_run(),
+            }.with_run(runs.remove("oyente")),
+            source,
+            solsa_version: crate_version!().to_owned(),
+            tools,
+        }
+    }
+
+    pub fn into_responses(self) -> tools::ToolResponses {
+        let mut runs = BTreeMap::new();
+        tools::ToolResponses {
+            solc: self.solc.into_response(
+                "solc",
+                &mut runs,
+                tools::SolcResponse::Success,
+                tools::SolcResponse::Failure,
+            ),
+            solium: self.solium.into_response(
+                "solium",
+                &mut runs,
+                tools::SoliumResponse::Success,
+                tools::SoliumResponse::Failure,
+            ),
+            mythril: self.mythril.into_response(
+                "mythril",
+                &mut runs,
+                tools::MythrilResponse::Success,
+                tools::MythrilResponse::Failure,
+            ),
+            // oyente's exit status is not kept in the report, its findings
+            // tell the same story
+            oyente: self.oyente.into_response(
+                "oyente",
+                &mut runs,
+                |o| {
+                    let issues = !o.findings().is_empty();
+                    tools::OyenteResponse::Success(o, issues)
+                },
+                tools::OyenteResponse::Failure,
+            ),
+            runs,
+        }
+    }
+}
+
+fn paint(color: bool, code: &str, s: &str) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, s)
+    } else {
+        s.to_owned()
+    }
+}
+
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "1;31",
+        Severity::Warning => "1;33",
+        Severity::Info => "1;36",
+    }
+}
+
+// Compiler style listing of every finding followed by per-tool status, meant
+// to be read straight from the terminal. With several contracts the status
+// is listed per contract.
+pub fn render_text(results: &[ContractResult], color: bool) -> String {
+    let tool_findings = all_tool_findings(results);
+    let all_findings = sorted_findings(&tool_findings);
+
+    let mut out = String::new();
+    for f in &all_findings {
+        let severity = format!("{:?}", f.severity).to_lowercase();
+        out.push_str(&format!(
+            "{}:{}:{}: {} [{}/{}] {}\n",
+            paint(color, "1", &f.filename),
+            f.line,
+            f.column,
+            paint(color, severity_color(f.severity), &severity),
+            f.tool,
+            f.rule,
+            f.message.split_whitespace().collect::<Vec<_>>().join(" ")
+        ));
+    }
+    if !all_findings.is_empty() {
+        out.push('\n');
+    }
+
+    let mut failed = vec![];
+    for r in results {
+        if results.len() > 1 {
+            out.push_str(&format!("{}\n", paint(color, "1", &r.contract)));
+        }
+        for t in &r.findings {
+            let status = match t.status {
+                ToolStatus::Ok => paint(color, "32", "ok"),
+                ToolStatus::Issues => paint(
+                    color,
+                    "33",
+                    &format!("{} issue(s) found", t.findings.len()),
+                ),
+                ToolStatus::Failed => match t.error {
+                    Some(ref e) => paint(color, "31", &format!("failed ({})", e.summary())),
+                    None => paint(color, "31", "failed"),
+                },
+                ToolStatus::NotRun => paint(color, "2", "not run"),
+            };
+            // a clean mythril run limited to a few modules says little of the rest
+            let modules = match r.responses.runs.get(&t.tool) {
+                Some(run) if run.modules_limited => {
+                    format!(" (only {})", run.modules.join(", "))
+                }
+                _ => String::new(),
+            };
+            out.push_str(&format!("{:<10}{}{}\n", t.tool, status, modules));
+            if t.status == ToolStatus::Failed {
+                failed.push(if results.len() > 1 {
+                    format!("{} ({})", t.tool, r.contract)
+                } else {
+                    t.tool.clone()
+                });
+            }
+        }
+    }
+
+    let count = |sev| all_findings.iter().filter(|f| f.severity == sev).count();
+    out.push_str(&format!(
+        "\n{} finding(s): {} error(s), {} warning(s), {} info",
+        all_findings.len(),
+        paint(color, severity_color(Severity::Error), &count(Severity::Error).to_string()),
+        paint(color, severity_color(Severity::Warning), &count(Severity::Warning).to_string()),
+        paint(color, severity_color(Severity::Info), &count(Severity::Info).to_string()),
+    ));
+    if !failed.is_empty() {
+        out.push_str(&format!(
+            "; {} {}",
+            paint(color, "31", "failed to run:"),
+            failed.join(", ")
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+fn sorted_findings(tool_findings: &[ToolFindings]) -> Vec<&Finding> {
+    let mut all_findings = tool_findings
+        .iter()
+        .flat_map(|t| t.findings.iter())
+        .collect::<Vec<_>>();
+    all_findings.sort_by_key(|f| (f.filename.clone(), f.line, f.column, f.tool.clone()));
+    all_findings
+}
+
+// Tools that failed to run, reported along with the findings so a failure
+// does not pass for a clean result in CI
+fn failures(results: &[ContractResult]) -> Vec<(&str, &str, &SolsaError)> {
+    results
+        .iter()
+        .flat_map(|r| {
+            r.findings
+                .iter()
+                .filter_map(move |t| t.error.as_ref().map(|e| (r.contract.as_str(), t.tool.as_str(), e)))
+        })
+        .collect()
+}
+
+fn all_tool_findings(results: &[ContractResult]) -> Vec<ToolFindings> {
+    results
+        .iter()
+        .flat_map(|r| r.findings.iter().cloned())
+        .collect()
+}
+
+// https://docs.gitlab.com/ee/user/project/merge_requests/code_quality.html
+pub fn render_gitlab(results: &[ContractResult]) -> String {
+    let tool_findings = all_tool_findings(results);
+    let all_findings = sorted_findings(&tool_findings);
+    let mut issues = all_findings
+        .iter()
+        .zip(fingerprints(all_findings.iter().cloned()))
+        .map(|(f, fingerprint)| {
+            json!({
+                "description": f.message,
+                "check_name": format!("{}/{}", f.tool, f.rule),
+                "fingerprint": fingerprint,
+                "severity": match f.severity {
+                    Severity::Error => "critical",
+                    Severity::Warning => "major",
+                    Severity::Info => "info",
+                },
+                "location": {
+                    "path": f.filename,
+                    "lines": { "begin": f.line.max(1) }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for (contract_file, tool, e) in failures(results) {
+        let key = [tool, e.kind(), contract_file].join("\u{0}");
+        issues.push(json!({
+            "description": format!("{} failed to run: {}", tool, e.summary()),
+            "check_name": format!("{}/{}", tool, e.kind()),
+            "fingerprint": format!("{:016x}", fnv1a(key.as_bytes())),
+            "severity": "info",
+            "location": {
+                "path": contract_file,
+                "lines": { "begin": 1 }
+            }
+        }));
+    }
+    serde_json::to_string_pretty(&issues).expect("Failed to serialize report")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_owned(),
+            '<' => "&lt;".to_owned(),
+            '>' => "&gt;".to_owned(),
+            '"' => "&quot;".to_owned(),
+            '\'' => "&apos;".to_owned(),
+            '\n' => "&#10;".to_owned(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// Checkstyle XML, as understood by e.g. Jenkins' warnings plugin
+pub fn render_checkstyle(results: &[ContractResult]) -> String {
+    let tool_findings = all_tool_findings(results);
+    let all_findings = sorted_findings(&tool_findings);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<checkstyle version=\"4.3\">\n");
+
+    // findings and tool failures of a file go in the same element
+    let mut files: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for f in &all_findings {
+        files.entry(f.filename.as_str()).or_default().push(format!(
+            "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"solsa.{}.{}\"/>\n",
+            f.line,
+            f.column,
+            match f.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            },
+            xml_escape(&f.message),
+            xml_escape(&f.tool),
+            xml_escape(&f.rule),
+        ));
+    }
+    for (contract_file, tool, e) in failures(results) {
+        files.entry(contract_file).or_default().push(format!(
+            "    <error line=\"1\" severity=\"info\" message=\"{}\" source=\"solsa.{}.{}\"/>\n",
+            xml_escape(&format!("{} failed to run: {}", tool, e.summary())),
+            xml_escape(tool),
+            e.kind(),
+        ));
+    }
+    for (file, errors) in files {
+        out.push_str(&format!("  <file name=\"{}\">\n", xml_escape(file)));
+        for error in errors {
+            out.push_str(&error);
+        }
+        out.push_str("  </file>\n");
+    }
+    out.push_str("</checkstyle>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use findings::finding;
+    use tools::ToolResponses;
+
+    fn result(contract: &str, findings: Vec<ToolFindings>) -> ContractResult {
+        ContractResult {
+            contract: contract.to_owned(),
+            source: None,
+            responses: ToolResponses::default(),
+            findings,
+        }
+    }
+
+    #[test]
+    fn xml_escape_escapes_markup_and_newlines() {
+        assert_eq!(
+            xml_escape("a < b && c > \"d\" 'e'\nf"),
+            "a &lt; b &amp;&amp; c &gt; &quot;d&quot; &apos;e&apos;&#10;f"
+        );
+    }
+
+    #[test]
+    fn checkstyle_escapes_messages() {
+        let results = vec![result(
+            "A.sol",
+            vec![ToolFindings {
+                tool: "solium".to_owned(),
+                status: ToolStatus::Issues,
+                findings: vec![finding("A.sol", 3, "use <emit> & \"events\"")],
+                error: None,
+            }],
+        )];
+        let xml = render_checkstyle(&results);
+        assert!(xml.contains("message=\"use &lt;emit&gt; &amp; &quot;events&quot;\""));
+    }
+
+    #[test]
+    fn checkstyle_has_one_file_element_per_path() {
+        let results = vec![result(
+            "A.sol",
+            vec![
+                ToolFindings {
+                    tool: "solium".to_owned(),
+                    status: ToolStatus::Issues,
+                    findings: vec![
+                        finding("A.sol", 3, "first"),
+                        Finding {
+                            severity: Severity::Error,
+                            ..finding("A.sol", 7, "second")
+                        },
+                    ],
+                    error: None,
+                },
+                ToolFindings {
+                    tool: "mythril".to_owned(),
+                    status: ToolStatus::Failed,
+                    findings: vec![],
+                    error: Some(SolsaError::Crash {
+                        tool: "mythril".to_owned(),
+                        status: Some(1),
+                        output: "boom".to_owned(),
+                    }),
+                },
+            ],
+        )];
+        let xml = render_checkstyle(&results);
+        assert_eq!(xml.matches("<file name=\"A.sol\">").count(), 1);
+        assert_eq!(xml.matches("</file>").count(), 1);
+        assert_eq!(xml.matches("<error ").count(), 3);
+        assert!(xml.contains("mythril failed to run"));
+    }
+
+    #[test]
+    fn float_filter_rejects_what_is_not_a_number() {
+        let mut ctx = Context::new();
+        ctx.add("coverage", &"99.5");
+        ctx.add("text", &"n/a");
+        ctx.add("list", &vec![1, 2]);
+        let mut tera = Tera::default();
+        tera.extend(&TERA).expect("Failed to extend templates");
+        tera.add_raw_template("ok", "{{ coverage | float }}").unwrap();
+        tera.add_raw_template("text", "{{ text | float }}").unwrap();
+        tera.add_raw_template("list", "{{ list | float }}").unwrap();
+        assert_eq!(tera.render("ok", &ctx).unwrap(), "99.5");
+        assert!(tera.render("text", &ctx).is_err());
+        assert!(tera.render("list", &ctx).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What the scheduler reports while it works through the jobs
+pub enum Progress<'a, J: 'a> {
+    Started { job: &'a J, total: usize },
+    Finished {
+        job: &'a J,
+        elapsed: Duration,
+        // jobs finished so far, this one included, out of `total`
+        done: usize,
+        total: usize,
+    },
+}
+
+enum Message<R> {
+    Started(usize),
+    Finished(usize, R, Duration),
+}
+
+// Runs the jobs on at most `workers` threads, in the order given. Results
+// are returned in that same order, progress is reported from the calling
+// thread.
+pub fn run<J, R, W, P>(jobs: Vec<J>, workers: usize, work: W, mut progress: P) -> Vec<(J, R)>
+where
+    J: Clone + Send + 'static,
+    R: Send + 'static,
+    W: Fn(&J) -> R + Send + Sync + 'static,
+    P: FnMut(Progress<J>),
+{
+    let total = jobs.len();
+    let queue = Arc::new(Mutex::new(
+        jobs.iter().cloned().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let work = Arc::new(work);
+    let (tx, rx) = mpsc::channel();
+
+    let handles = (0..workers.max(1).min(total))
+        .map(|_| {
+            let queue = queue.clone();
+            let work = work.clone();
+            let tx = tx.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().expect("Job queue poisoned").pop_front();
+                let (i, job) = match next {
+                    Some(j) => j,
+                    None => break,
+                };
+                let _ = tx.send(Message::Started(i));
+                let start = Instant::now();
+                let result = work(&job);
+                let _ = tx.send(Message::Finished(i, result, start.elapsed()));
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(tx);
+
+    let mut results = (0..total).map(|_| None).collect::<Vec<Option<R>>>();
+    let mut done = 0;
+    for message in rx {
+        match message {
+            Message::Started(i) => progress(Progress::Started {
+                job: &jobs[i],
+                total,
+            }),
+            Message::Finished(i, result, elapsed) => {
+                done += 1;
+                results[i] = Some(result);
+                progress(Progress::Finished {
+                    job: &jobs[i],
+                    elapsed,
+                    done,
+                    total,
+                });
+            }
+        }
+    }
+    for h in handles {
+        h.join().expect("Job failed");
+    }
+
+    jobs.into_iter()
+        .zip(results.into_iter().map(|r| r.expect("Job did not finish")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn jobs_start_in_the_order_given() {
+        let mut started = Vec::new();
+        let results = run(vec![3, 1, 2], 1, |j: &u32| j * 10, |p| {
+            if let Progress::Started { job, .. } = p {
+                started.push(*job);
+            }
+        });
+        assert_eq!(started, vec![3, 1, 2]);
+        assert_eq!(results, vec![(3, 30), (1, 10), (2, 20)]);
+    }
+
+    #[test]
+    fn no_more_than_workers_at_once() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (r, m) = (running.clone(), most.clone());
+        let results = run(
+            (0..6).collect(),
+            2,
+            move |_: &u32| {
+                let now = r.fetch_add(1, Ordering::SeqCst) + 1;
+                m.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                r.fetch_sub(1, Ordering::SeqCst);
+            },
+            |_| {},
+        );
+        assert_eq!(results.len(), 6);
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+}
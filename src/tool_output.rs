@@ -1,70 +1,72 @@
 use std::collections::HashMap;
 
+use findings::{parse_gcc_line, Finding, Severity};
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolcOutput {
-    contracts: HashMap<String, SolcContract>,
-    version: String,
+    pub contracts: HashMap<String, SolcContract>,
+    pub version: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct SolcContract {
-    abi: String,
-    bin: String,
+pub struct SolcContract {
+    pub abi: String,
+    pub bin: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MythrilOutput {
     pub success: bool,
-    error: Option<String>,
-    issues: Vec<MythrilIssue>,
+    pub error: Option<String>,
+    pub issues: Vec<MythrilIssue>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct MythrilIssue {
-    title: String,
-    description: String,
+pub struct MythrilIssue {
+    pub title: String,
+    pub description: String,
     #[serde(rename = "type")]
-    type_: String,
-    code: String,
-    function: String,
-    debug: String,
-    filename: String,
-    lineno: u32,
-    address: u32,
+    pub type_: String,
+    pub code: String,
+    pub function: String,
+    pub debug: String,
+    pub filename: String,
+    pub lineno: u32,
+    pub address: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OyenteOutput {
     #[serde(flatten)]
-    files: HashMap<String, OyenteSolidityFile>,
+    pub files: HashMap<String, OyenteSolidityFile>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct OyenteSolidityFile {
+pub struct OyenteSolidityFile {
     #[serde(flatten)]
-    contracts: HashMap<String, OyenteContract>,
+    pub contracts: HashMap<String, OyenteContract>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct OyenteContract {
-    evm_code_coverage: String,
-    success: Option<bool>,
-    vulnerabilities: OyenteVulnerabilities,
+pub struct OyenteContract {
+    pub evm_code_coverage: String,
+    pub success: Option<bool>,
+    pub vulnerabilities: OyenteVulnerabilities,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct OyenteVulnerabilities {
-    integer_overflow: Vec<String>,
-    integer_underflow: Vec<String>,
-    callstack: Vec<String>,
-    money_concurrency: Vec<Vec<String>>,
-    time_dependency: Vec<String>,
-    reentrancy: Vec<String>,
-    assertion_failure: Vec<String>,
-    parity_multisig_bug_2: Vec<String>,
+pub struct OyenteVulnerabilities {
+    pub integer_overflow: Vec<String>,
+    pub integer_underflow: Vec<String>,
+    pub callstack: Vec<String>,
+    pub money_concurrency: Vec<Vec<String>>,
+    pub time_dependency: Vec<String>,
+    pub reentrancy: Vec<String>,
+    pub assertion_failure: Vec<String>,
+    pub parity_multisig_bug_2: Vec<String>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SoliumIssue {
     pub filename: String,
     pub line: u32,
@@ -72,3 +74,95 @@ pub struct SoliumIssue {
     pub type_: String,
     pub message: String,
 }
+
+impl MythrilOutput {
+    pub fn findings(&self) -> Vec<Finding> {
+        self.issues
+            .iter()
+            .map(|i| Finding {
+                tool: "mythril".to_owned(),
+                severity: Severity::from_label(&i.type_),
+                filename: i.filename.clone(),
+                line: i.lineno,
+                column: 0,
+                contract: i.filename.clone(),
+                rule: i.title.clone(),
+                message: i.description.trim().to_owned(),
+                details: Some(i.debug.clone()),
+            })
+            .collect()
+    }
+}
+
+impl OyenteOutput {
+    pub fn findings(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (filename, file) in &self.files {
+            for (contract_name, contract) in &file.contracts {
+                for (rule, warning) in contract.vulnerabilities.warnings() {
+                    // warnings start with a gcc style header line followed by
+                    // the offending code and conditions
+                    let mut f = warning
+                        .lines()
+                        .next()
+                        .and_then(|l| parse_gcc_line("oyente", l))
+                        .unwrap_or_else(|| Finding {
+                            tool: "oyente".to_owned(),
+                            severity: Severity::Warning,
+                            filename: filename.clone(),
+                            line: 0,
+                            column: 0,
+                            contract: String::new(),
+                            rule: String::new(),
+                            message: warning.trim().to_owned(),
+                            details: None,
+                        });
+                    f.contract = contract_name.clone();
+                    f.rule = rule.to_owned();
+                    f.details = Some(warning.clone());
+                    findings.push(f);
+                }
+            }
+        }
+        findings
+    }
+}
+
+impl OyenteVulnerabilities {
+    fn warnings(&self) -> Vec<(&'static str, &String)> {
+        let lists = [
+            ("integer_overflow", &self.integer_overflow),
+            ("integer_underflow", &self.integer_underflow),
+            ("callstack", &self.callstack),
+            ("time_dependency", &self.time_dependency),
+            ("reentrancy", &self.reentrancy),
+            ("assertion_failure", &self.assertion_failure),
+            ("parity_multisig_bug_2", &self.parity_multisig_bug_2),
+        ];
+        let mut warnings = lists
+            .iter()
+            .flat_map(|&(rule, l)| l.iter().map(move |w| (rule, w)))
+            .collect::<Vec<_>>();
+        // transaction ordering dependence is reported as a list of flows
+        for flow in &self.money_concurrency {
+            warnings.extend(flow.first().map(|w| ("money_concurrency", w)));
+        }
+        warnings
+    }
+}
+
+impl SoliumIssue {
+    pub fn to_finding(&self) -> Finding {
+        Finding {
+            tool: "solium".to_owned(),
+            severity: Severity::from_label(&self.type_),
+            filename: self.filename.clone(),
+            line: self.line,
+            column: self.column,
+            contract: self.filename.clone(),
+            rule: self.type_.trim().to_lowercase(),
+            message: self.message.trim().to_owned(),
+            details: None,
+        }
+    }
+}
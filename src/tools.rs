@@ -1,32 +1,87 @@
-use std::env;
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use serde::de::DeserializeOwned;
 use serde_json;
 
+use backend::Backends;
+use error::SolsaError;
 use tool_output;
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum SolcResponse {
     Success(tool_output::SolcOutput),
-    Failure(String),
+    Failure(SolsaError),
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum MythrilResponse {
     Success(tool_output::MythrilOutput),
-    Failure(String),
+    Failure(SolsaError),
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum OyenteResponse {
     Success(tool_output::OyenteOutput, bool),
-    Failure(String),
+    Failure(SolsaError),
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub enum SoliumResponse {
     Success(Vec<tool_output::SoliumIssue>),
-    Failure(String),
+    Failure(SolsaError),
+}
+
+/// When a tool ran, for how long and how it exited
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ToolRun {
+    /// Unix time, in seconds
+    pub started: u64,
+    /// In seconds
+    pub duration: f64,
+    /// `None` when the tool could not be started, or was stopped
+    pub exit_status: Option<i32>,
+    /// The result was reused from the cache, the run is that of the result
+    #[serde(default)]
+    pub cached: bool,
+    /// Detection modules that ran, only mythril has any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<String>,
+    /// Some of the modules were left out with `--mythril-modules`
+    #[serde(default)]
+    pub modules_limited: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ToolResponses {
+    pub solc: Option<SolcResponse>,
+    pub solium: Option<SoliumResponse>,
+    pub mythril: Option<MythrilResponse>,
+    pub oyente: Option<OyenteResponse>,
+    /// By tool, for the tools that ran
+    pub runs: BTreeMap<String, ToolRun>,
+}
+
+impl ToolResponses {
+    /// Replaces the responses, and runs, of the tools that ran in `other`
+    pub fn update(&mut self, other: ToolResponses) {
+        if other.solc.is_some() {
+            self.solc = other.solc;
+        }
+        if other.solium.is_some() {
+            self.solium = other.solium;
+        }
+        if other.mythril.is_some() {
+            self.mythril = other.mythril;
+        }
+        if other.oyente.is_some() {
+            self.oyente = other.oyente;
+        }
+        self.runs.extend(other.runs);
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -37,107 +92,342 @@ pub enum AnalysisDepth {
     Deepest,
 }
 
-pub fn run_solc(solidity_contract_path: &str) -> Option<SolcResponse> {
-    let mut cmd = docker_cmd!("solc");
-    cmd.arg("--pretty-json")
-        .arg("--combined-json")
-        .arg("abi,bin")
-        .arg("--allow-paths")
-        .arg(".")
-        .arg(solidity_contract_path);
-    cmd.output().ok().and_then(|output| {
+// Failing to keep the raw output is not worth failing the tool run for
+fn keep_raw(dir: &Path, tool: &str, contract_path: &str, output: &Output) {
+    let name = contract_path.replace(&['/', '\\'][..], "_");
+    for &(stream, data) in &[("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        let path = dir.join(format!("{}.{}.{}", name, tool, stream));
+        match fs::create_dir_all(dir).and_then(|_| fs::write(&path, data)) {
+            Ok(()) => debug!("{}: {} saved to {}", tool, stream, path.display()),
+            Err(e) => warn!(
+                "{}: could not keep raw output, {}",
+                tool,
+                SolsaError::io(&path.display().to_string(), &e)
+            ),
+        }
+    }
+}
+
+fn run(
+    backends: &Backends,
+    tool: &str,
+    contract_path: &str,
+    args: &[&str],
+) -> (Result<Output, SolsaError>, ToolRun) {
+    let started = SystemTime::now();
+    let clock = Instant::now();
+    let output = backends.command(tool).and_then(|mut cmd| {
+        cmd.args(args);
+        cmd.output()
+    });
+    let elapsed = clock.elapsed();
+    if let (Some(dir), Ok(o)) = (backends.keep_raw.as_ref(), output.as_ref()) {
+        keep_raw(dir, tool, contract_path, o);
+    }
+    let run = ToolRun {
+        started: started
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        duration: elapsed.as_secs() as f64 + f64::from(elapsed.subsec_millis()) / 1000.0,
+        exit_status: output.as_ref().ok().and_then(|o| o.status.code()),
+        cached: false,
+        modules: vec![],
+        modules_limited: false,
+    };
+    (output, run)
+}
+
+// everything the tool printed, for tools that report errors on either stream
+fn crash(tool: &str, output: &Output) -> SolsaError {
+    SolsaError::Crash {
+        tool: tool.to_owned(),
+        status: output.status.code(),
+        output: String::from_utf8_lossy(&[&output.stdout[..], &output.stderr[..]].concat())
+            .into_owned(),
+    }
+}
+
+fn parse_json<T: DeserializeOwned>(tool: &str, output: &[u8]) -> Result<T, SolsaError> {
+    serde_json::from_slice(output).map_err(|e| SolsaError::Parse {
+        tool: tool.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+pub fn flattener_args(solidity_contract_path: &str) -> Vec<&str> {
+    vec![solidity_contract_path]
+}
+
+pub fn solc_args(solidity_contract_path: &str) -> Vec<&str> {
+    vec![
+        "--pretty-json",
+        "--combined-json",
+        "abi,bin",
+        "--allow-paths",
+        ".",
+        solidity_contract_path,
+    ]
+}
+
+// mythril's `--max-depth` for each analysis depth
+pub fn mythril_depth(analysis_depth: AnalysisDepth) -> u32 {
+    match analysis_depth {
+        AnalysisDepth::Shallow => 4,
+        AnalysisDepth::Deep => 8,
+        AnalysisDepth::Deeper => 22,
+        AnalysisDepth::Deepest => 80,
+    }
+}
+
+// oyente's `-dl` (depth limit) for each analysis depth
+pub fn oyente_depth(analysis_depth: AnalysisDepth) -> u32 {
+    match analysis_depth {
+        AnalysisDepth::Shallow => 20,
+        AnalysisDepth::Deep => 50,
+        AnalysisDepth::Deeper => 80,
+        AnalysisDepth::Deepest => 250,
+    }
+}
+
+/// Detection modules of mythril 0.18, any of which `-m` can run
+pub const MYTHRIL_MODULES: &[&str] = &[
+    "delegatecall",
+    "dependence_on_predictable_vars",
+    "deprecated_ops",
+    "ether_send",
+    "exceptions",
+    "external_calls",
+    "integer",
+    "multiple_sends",
+    "suicide",
+    "transaction_order_dependence",
+    "unchecked_retval",
+];
+
+// flag and value of each numeric option that is set
+fn option_args(options: &[(&str, Option<u64>)]) -> Vec<String> {
+    options
+        .iter()
+        .filter_map(|&(flag, value)| value.map(|v| vec![flag.to_owned(), v.to_string()]))
+        .flatten()
+        .collect()
+}
+
+/// Options mythril runs with, those not set are left to mythril except for
+/// the depth, which the analysis depth gives
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MythrilOptions {
+    /// `--max-depth`
+    pub max_depth: Option<u32>,
+    /// `--execution-timeout`, seconds of symbolic execution
+    pub execution_timeout: Option<u64>,
+    /// `--max-transaction-count`
+    pub transaction_count: Option<u32>,
+    /// `-m`, detection modules to run, all of them when empty
+    pub modules: Vec<String>,
+}
+
+impl MythrilOptions {
+    /// These options, with the analysis depth's default for those not set
+    pub fn with_depth(&self, analysis_depth: AnalysisDepth) -> MythrilOptions {
+        MythrilOptions {
+            max_depth: Some(self.max_depth.unwrap_or_else(|| mythril_depth(analysis_depth))),
+            ..self.clone()
+        }
+    }
+
+    /// Arguments for the options that are set
+    pub fn args(&self) -> Vec<String> {
+        let mut args = option_args(&[
+            ("--max-depth", self.max_depth.map(u64::from)),
+            ("--execution-timeout", self.execution_timeout),
+            ("--max-transaction-count", self.transaction_count.map(u64::from)),
+        ]);
+        if !self.modules.is_empty() {
+            args.extend(vec!["-m".to_owned(), self.modules.join(",")]);
+        }
+        args
+    }
+}
+
+/// Options oyente runs with, those not set are left to oyente except for
+/// the depth limit, which the analysis depth gives
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OyenteOptions {
+    /// `-dl`
+    pub depth_limit: Option<u32>,
+    /// `-gl`
+    pub gas_limit: Option<u64>,
+    /// `-ll`
+    pub loop_limit: Option<u32>,
+    /// `-t`, milliseconds the solver may take on a single query
+    pub solver_timeout: Option<u64>,
+    /// `-glt`, seconds of symbolic execution
+    pub global_timeout: Option<u64>,
+}
+
+impl OyenteOptions {
+    /// These options, with the analysis depth's default for those not set
+    pub fn with_depth(&self, analysis_depth: AnalysisDepth) -> OyenteOptions {
+        OyenteOptions {
+            depth_limit: Some(self.depth_limit.unwrap_or_else(|| oyente_depth(analysis_depth))),
+            ..self.clone()
+        }
+    }
+
+    /// Arguments for the options that are set
+    pub fn args(&self) -> Vec<String> {
+        option_args(&[
+            ("-dl", self.depth_limit.map(u64::from)),
+            ("-gl", self.gas_limit),
+            ("-ll", self.loop_limit.map(u64::from)),
+            ("-t", self.solver_timeout),
+            ("-glt", self.global_timeout),
+        ])
+    }
+}
+
+pub fn mythril_args(solidity_contract_path: &str, options: &MythrilOptions) -> Vec<String> {
+    let mut args = vec!["-xo".to_owned(), "json".to_owned()];
+    args.extend(options.args());
+    args.push(solidity_contract_path.to_owned());
+    args
+}
+
+pub fn oyente_args(solidity_contract_path: &str, options: &OyenteOptions) -> Vec<String> {
+    let mut args = vec!["-w".to_owned(), "-ce".to_owned(), "-a".to_owned()];
+    args.extend(options.args());
+    args.extend(vec![
+        "-ap".to_owned(),
+        ".".to_owned(),
+        "-s".to_owned(),
+        solidity_contract_path.to_owned(),
+    ]);
+    args
+}
+
+pub fn solium_args(solidity_contract_path: &str) -> Vec<&str> {
+    vec!["-R", "gcc", "-f", solidity_contract_path]
+}
+
+/// Arguments a tool is run with, for any of the tools `run_*` runs
+pub fn args(
+    tool: &str,
+    solidity_contract_path: &str,
+    mythril: &MythrilOptions,
+    oyente: &OyenteOptions,
+) -> Vec<String> {
+    let to_owned = |args: Vec<&str>| args.into_iter().map(|a| a.to_owned()).collect();
+    match tool {
+        "solc" => to_owned(solc_args(solidity_contract_path)),
+        "solium" => to_owned(solium_args(solidity_contract_path)),
+        "mythril" => mythril_args(solidity_contract_path, mythril),
+        "oyente" => oyente_args(solidity_contract_path, oyente),
+        _ => to_owned(flattener_args(solidity_contract_path)),
+    }
+}
+
+pub fn run_flattener(backends: &Backends, solidity_contract_path: &str) -> Result<String, SolsaError> {
+    let output = run(
+        backends,
+        "solidity-flattener",
+        solidity_contract_path,
+        &flattener_args(solidity_contract_path),
+    ).0?;
+    if !output.status.success() {
+        return Err(crash("solidity-flattener", &output));
+    }
+    String::from_utf8(output.stdout).map_err(|e| SolsaError::Parse {
+        tool: "solidity-flattener".to_owned(),
+        message: e.to_string(),
+    })
+}
+
+pub fn run_solc(backends: &Backends, solidity_contract_path: &str) -> (SolcResponse, ToolRun) {
+    let (output, tool_run) = run(
+        backends,
+        "solc",
+        solidity_contract_path,
+        &solc_args(solidity_contract_path),
+    );
+    let result = output.and_then(|output| {
         if output.status.success() {
-            String::from_utf8(output.stdout)
-                .ok()
-                .and_then(|s| serde_json::from_str(&s).ok())
-                .and_then(|o| Some(SolcResponse::Success(o)))
+            parse_json("solc", &output.stdout)
         } else {
-            String::from_utf8(output.stderr)
-                .ok()
-                .and_then(|s| Some(SolcResponse::Failure(s)))
+            Err(crash("solc", &output))
         }
-    })
+    });
+    let response = match result {
+        Ok(o) => SolcResponse::Success(o),
+        Err(e) => SolcResponse::Failure(e),
+    };
+    (response, tool_run)
 }
 
 pub fn run_mythril(
+    backends: &Backends,
     solidity_contract_path: &str,
-    analysis_depth: AnalysisDepth,
-) -> Option<MythrilResponse> {
-    let depth_value = match analysis_depth {
-        AnalysisDepth::Shallow => "4",
-        AnalysisDepth::Deep => "8",
-        AnalysisDepth::Deeper => "22",
-        AnalysisDepth::Deepest => "80",
+    options: &MythrilOptions,
+) -> (MythrilResponse, ToolRun) {
+    let args = mythril_args(solidity_contract_path, options);
+    let (output, mut tool_run) = run(
+        backends,
+        "mythril",
+        solidity_contract_path,
+        &args.iter().map(|a| a.as_str()).collect::<Vec<_>>(),
+    );
+    // a clean result only covers the modules that ran
+    tool_run.modules = if options.modules.is_empty() {
+        MYTHRIL_MODULES.iter().map(|m| m.to_string()).collect()
+    } else {
+        options.modules.clone()
     };
-
-    let mut cmd = docker_cmd!("mythril");
-    cmd.arg("-xo")
-        .arg("json")
-        .arg("--max-depth")
-        .arg(depth_value)
-        .arg(solidity_contract_path);
-    cmd.output().ok().and_then(|output| {
+    tool_run.modules_limited = MYTHRIL_MODULES
+        .iter()
+        .any(|m| !tool_run.modules.iter().any(|r| r == m));
+    let result = output.and_then(|output| {
         if output.status.success() {
-            String::from_utf8(output.stdout)
-                .ok()
-                .and_then(|s| match serde_json::from_str(&s) {
-                    Ok(o) => Some(MythrilResponse::Success(o)),
-                    Err(s) => Some(MythrilResponse::Failure(format!(
-                        "Error deserializing: {:?}",
-                        &s
-                    ))),
-                })
-                .or_else(|| {
-                    Some(MythrilResponse::Failure(
-                        "Unknown error deserializing".to_owned(),
-                    ))
-                })
+            parse_json("mythril", &output.stdout)
         } else {
-            String::from_utf8(output.stderr)
-                .ok()
-                .and_then(|s| Some(MythrilResponse::Failure(s)))
-                .or_else(|| Some(MythrilResponse::Failure("Unknown error".to_owned())))
+            Err(crash("mythril", &output))
         }
-    })
+    });
+    let response = match result {
+        Ok(o) => MythrilResponse::Success(o),
+        Err(e) => MythrilResponse::Failure(e),
+    };
+    (response, tool_run)
 }
 
 pub fn run_oyente(
+    backends: &Backends,
     solidity_contract_path: &str,
-    analysis_depth: AnalysisDepth,
-) -> Option<OyenteResponse> {
-    let depth_value = match analysis_depth {
-        AnalysisDepth::Shallow => "20",
-        AnalysisDepth::Deep => "50",
-        AnalysisDepth::Deeper => "80",
-        AnalysisDepth::Deepest => "250",
+    options: &OyenteOptions,
+) -> (OyenteResponse, ToolRun) {
+    let args = oyente_args(solidity_contract_path, options);
+    let (output, tool_run) = run(
+        backends,
+        "oyente",
+        solidity_contract_path,
+        &args.iter().map(|a| a.as_str()).collect::<Vec<_>>(),
+    );
+    let result = output.and_then(|output| {
+        // oyente exits with an error when it finds issues, so its output
+        // is all that tells the two apart
+        match parse_json("oyente", &output.stdout) {
+            Ok(o) => Ok((o, !output.status.success())),
+            Err(_) if !output.status.success() => Err(crash("oyente", &output)),
+            Err(e) => Err(e),
+        }
+    });
+    let response = match result {
+        Ok((o, failed)) => OyenteResponse::Success(o, failed),
+        Err(e) => OyenteResponse::Failure(e),
     };
-    let mut cmd = docker_cmd!("oyente");
-    cmd.arg("-w")
-        .arg("-ce")
-        .arg("-a")
-        .arg("-dl")
-        .arg(depth_value)
-        .arg("-ap")
-        .arg(".")
-        .arg("-s")
-        .arg(solidity_contract_path);
-    cmd.output().ok().and_then(|output| {
-        String::from_utf8(output.stdout.clone())
-            .ok()
-            .and_then(|s| match serde_json::from_str(&s) {
-                Ok(o) => Some(OyenteResponse::Success(o, !output.status.success())),
-                Err(s) => Some(OyenteResponse::Failure(format!(
-                    "Error deserializing: {:?}",
-                    &s
-                ))),
-            })
-            .or_else(|| {
-                String::from_utf8(output.stderr)
-                    .ok()
-                    .and_then(|s| Some(OyenteResponse::Failure(s)))
-                    .or_else(|| Some(OyenteResponse::Failure("Unknown error".to_owned())))
-            })
-    })
+    (response, tool_run)
 }
 
 fn parse_solium_response(o: &str) -> Vec<tool_output::SoliumIssue> {
@@ -160,36 +450,79 @@ fn parse_solium_response(o: &str) -> Vec<tool_output::SoliumIssue> {
 
 // from https://github.com/duaraghav8/Solium/blob/master/lib/reporters/gcc.js
 // filename + ":" + error.line + ":" + error.column + ": " + error.type + ": " + error.message
-pub fn run_solium(solidity_contract_path: &str) -> Option<SoliumResponse> {
-    let mut cmd = docker_cmd!("solium");
-    cmd.arg("-R")
-        .arg("gcc")
-        .arg("-f")
-        .arg(solidity_contract_path);
-    cmd.output().ok().and_then(|output| {
-        if output.status.success() {
-            String::from_utf8(output.stdout)
-                .ok()
-                .and_then(|o| {
-                    let resp = parse_solium_response(&o);
-                    Some(SoliumResponse::Success(resp))
-                })
-                .or_else(|| {
-                    Some(SoliumResponse::Failure(
-                        "Unknown error deserializing".to_owned(),
-                    ))
-                })
+pub fn run_solium(backends: &Backends, solidity_contract_path: &str) -> (SoliumResponse, ToolRun) {
+    let (output, tool_run) = run(
+        backends,
+        "solium",
+        solidity_contract_path,
+        &solium_args(solidity_contract_path),
+    );
+    let result = output.and_then(|output| {
+        let issues = parse_solium_response(&String::from_utf8_lossy(&output.stdout));
+        // solium exits with an error when it finds issues
+        if output.status.success() || !issues.is_empty() {
+            Ok(issues)
         } else {
-            String::from_utf8(output.stdout.clone()).ok().and_then(|o| {
-                let resp = parse_solium_response(&o);
-                match resp.len() {
-                    0 => String::from_utf8([&output.stdout[..], &output.stderr[..]].concat())
-                        .ok()
-                        .and_then(|s| Some(SoliumResponse::Failure(s)))
-                        .or_else(|| Some(SoliumResponse::Failure("Unknown error".to_owned()))),
-                    _ => Some(SoliumResponse::Success(resp)),
-                }
-            })
+            Err(crash("solium", &output))
         }
-    })
+    });
+    let response = match result {
+        Ok(issues) => SoliumResponse::Success(issues),
+        Err(e) => SoliumResponse::Failure(e),
+    };
+    (response, tool_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[String]) -> String {
+        args.join(" ")
+    }
+
+    #[test]
+    fn default_mythril_args_only_set_the_depth() {
+        let options = MythrilOptions::default().with_depth(AnalysisDepth::Shallow);
+        assert_eq!(argv(&mythril_args("A.sol", &options)), "-xo json --max-depth 4 A.sol");
+    }
+
+    #[test]
+    fn custom_mythril_args() {
+        let options = MythrilOptions {
+            max_depth: Some(30),
+            execution_timeout: Some(120),
+            transaction_count: Some(3),
+            modules: vec!["suicide".to_owned(), "integer".to_owned()],
+        }.with_depth(AnalysisDepth::Deepest);
+        assert_eq!(
+            argv(&mythril_args("A.sol", &options)),
+            "-xo json --max-depth 30 --execution-timeout 120 --max-transaction-count 3 \
+             -m suicide,integer A.sol"
+        );
+    }
+
+    #[test]
+    fn default_oyente_args_only_set_the_depth_limit() {
+        let options = OyenteOptions::default().with_depth(AnalysisDepth::Deep);
+        assert_eq!(
+            argv(&oyente_args("A.sol", &options)),
+            "-w -ce -a -dl 50 -ap . -s A.sol"
+        );
+    }
+
+    #[test]
+    fn custom_oyente_args() {
+        let options = OyenteOptions {
+            depth_limit: None,
+            gas_limit: Some(8_000_000),
+            loop_limit: Some(20),
+            solver_timeout: Some(1000),
+            global_timeout: Some(300),
+        }.with_depth(AnalysisDepth::Deeper);
+        assert_eq!(
+            argv(&oyente_args("A.sol", &options)),
+            "-w -ce -a -dl 80 -gl 8000000 -ll 20 -t 1000 -glt 300 -ap . -s A.sol"
+        );
+    }
 }
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use analysis::{Analysis, SLOW_TOOLS};
+use backend::ToolInfo;
+use diff;
+use error::SolsaError;
+use findings::ToolFindings;
+use report;
+use tools;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// import "./A.sol";
+// import {A, B} from "./A.sol";
+// import * as A from "./A.sol";
+fn imported_paths(source: &str) -> Vec<String> {
+    source
+        .split(';')
+        .map(|statement| statement.trim())
+        .filter(|statement| statement.starts_with("import"))
+        .filter_map(|statement| {
+            let quote = statement.find(&['"', '\''][..])?;
+            let rest = &statement[quote + 1..];
+            let end = rest.find(&['"', '\''][..])?;
+            Some(rest[..end].to_owned())
+        })
+        .collect()
+}
+
+// The contract and every local file it (transitively) imports, imports that
+// can not be found locally (e.g. remappings) are skipped
+fn watched_files(contract_path: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::from(contract_path)];
+    while let Some(path) = pending.pop() {
+        if files.contains(&path) {
+            continue;
+        }
+        if let Ok(source) = fs::read_to_string(&path) {
+            let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            for import in imported_paths(&source) {
+                let imported = dir.join(&import);
+                if imported.exists() {
+                    pending.push(imported);
+                }
+            }
+        }
+        files.push(path);
+    }
+    files
+}
+
+fn snapshot(files: &[PathBuf]) -> BTreeMap<PathBuf, Option<SystemTime>> {
+    files
+        .iter()
+        .map(|f| {
+            (
+                f.clone(),
+                fs::metadata(f).and_then(|m| m.modified()).ok(),
+            )
+        })
+        .collect()
+}
+
+
+struct Watcher {
+    analysis: Analysis,
+    contract_path: String,
+    report_path: String,
+    color: bool,
+    responses: tools::ToolResponses,
+    tool_infos: Vec<ToolInfo>,
+    previous: Vec<ToolFindings>,
+    // the slow tools have reported back at least once, before that the
+    // report would show them as not run
+    complete: bool,
+}
+
+impl Watcher {
+    // Prints what changed for the tools that just ran and refreshes the report
+    fn publish(&mut self, updated: &[String]) {
+        let current = self.analysis.findings(&self.responses);
+        let changes = diff::diff(
+            &only(&self.previous, updated),
+            &only(&current, updated),
+        );
+        let (new, fixed, _) = diff::totals(&changes);
+
+        println!("[{}] {} finished", now(), updated.join(", "));
+        if new + fixed > 0 || changes.iter().any(|t| t.old_status != t.new_status) {
+            print!("{}", diff::render_text(&changes, self.color, false));
+        } else {
+            println!("no changes");
+        }
+
+        if self.complete {
+            let ctx = report::report_context(
+                &self.contract_path,
+                None,
+                &current,
+                &self.responses,
+                &self.tool_infos,
+            );
+            let html = report::TERA
+                .render("index.html", &ctx)
+                .expect("Failed to render reports");
+            // keep watching, the next change may well be written fine
+            if let Err(e) = fs::write(&self.report_path, &html) {
+                eprintln!("error: {}", SolsaError::io(&self.report_path, &e));
+            }
+        }
+        // tools still running keep their last reported results
+        self.previous.retain(|t| !updated.contains(&t.tool));
+        self.previous.extend(only(&current, updated));
+    }
+}
+
+fn only(tool_findings: &[ToolFindings], tools: &[String]) -> Vec<ToolFindings> {
+    tool_findings
+        .iter()
+        .filter(|t| tools.contains(&t.tool))
+        .cloned()
+        .collect()
+}
+
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        (secs / 3600) % 24,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+// Reruns the analysis whenever the contract or its imports change. solc and
+// solium give immediate feedback, mythril and oyente run in the background.
+// There is one background run at a time, so quick edits do not pile up
+// containers: changes made meanwhile wait for it, only the latest of them
+// gets analyzed, and results from runs superseded by a newer change are
+// dropped.
+pub fn watch(analysis: Analysis, contract_path: &str, report_path: &str, color: bool) {
+    let tool_infos = analysis.tool_infos();
+    let (slow_tools, fast_tools): (Vec<String>, Vec<String>) = analysis
+        .tools()
+        .iter()
+        .cloned()
+        .partition(|t| SLOW_TOOLS.contains(&t.as_str()));
+    let mut watcher = Watcher {
+        analysis,
+        contract_path: contract_path.to_owned(),
+        report_path: report_path.to_owned(),
+        color,
+        responses: tools::ToolResponses::default(),
+        tool_infos,
+        previous: Vec::new(),
+        // with no slow tools selected, the fast ones complete the report
+        complete: slow_tools.is_empty(),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut generation = 0u64;
+    let mut files = Vec::new();
+    let mut last_snapshot = BTreeMap::new();
+    // the background run is going, and the source of a change still to be
+    // analyzed by the slow tools
+    let mut running = false;
+    let mut pending: Option<Option<String>> = None;
+
+    loop {
+        let current_files = watched_files(&watcher.contract_path);
+        let current_snapshot = snapshot(&current_files);
+        if current_snapshot != last_snapshot {
+            if !files.is_empty() {
+                println!("\n[{}] change detected, re-running analysis", now());
+            }
+            files = current_files;
+            last_snapshot = current_snapshot;
+            generation += 1;
+
+            let source = watcher
+                .analysis
+                .flatten(&watcher.contract_path)
+                .and_then(|f| f.ok());
+            let fast = watcher
+                .analysis
+                .run_fast_tools(&watcher.contract_path, source.as_deref());
+            watcher.responses.update(fast);
+            if !fast_tools.is_empty() {
+                watcher.publish(&fast_tools);
+            }
+
+            if slow_tools.is_empty() {
+                println!(
+                    "[{}] report written to {}, watching {} file(s)",
+                    now(),
+                    watcher.report_path,
+                    files.len()
+                );
+            } else {
+                pending = Some(source);
+                if running {
+                    println!(
+                        "[{}] {} will run once their previous run is over, watching {} file(s)",
+                        now(),
+                        slow_tools.join(", "),
+                        files.len()
+                    );
+                } else {
+                    println!(
+                        "[{}] {} running in the background, watching {} file(s)",
+                        now(),
+                        slow_tools.join(", "),
+                        files.len()
+                    );
+                }
+            }
+        }
+
+        while let Ok((g, slow)) = rx.try_recv() {
+            running = false;
+            if g == generation {
+                watcher.responses.update(slow);
+                watcher.complete = true;
+                watcher.publish(&slow_tools);
+                println!("[{}] report written to {}", now(), watcher.report_path);
+            }
+        }
+
+        if !running {
+            if let Some(source) = pending.take() {
+                running = true;
+                let tx = tx.clone();
+                let analysis = watcher.analysis.clone();
+                let contract_path = watcher.contract_path.clone();
+                let this_generation = generation;
+                thread::spawn(move || {
+                    let slow = analysis.run_slow_tools(&contract_path, source.as_deref());
+                    let _ = tx.send((this_generation, slow));
+                });
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}